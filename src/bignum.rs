@@ -0,0 +1,647 @@
+use std::cmp::Ordering;
+
+/// An arbitrary-precision integer, integers that overflow an `i64` are promoted to it.
+/// The magnitude is stored little-endian in base 2^32 without trailing zero limbs,
+/// so zero is an empty magnitude and is never negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+/// An exact decimal number, `unscaled / 10^scale`, written with a `d` suffix like `1.10d`.
+/// The scale is kept through addition and subtraction so `1.10d + 2d` prints as `3.10`,
+/// but it doesn't take part in comparisons, `1.10d == 1.1d`.
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    unscaled: BigInt,
+    scale: u32,
+}
+
+/// Bits the result of a power or a left shift may have, larger results are refused instead of computed.
+pub const MAX_BITS: u64 = 1 << 20;
+
+/// Extra fractional digits a decimal division keeps beyond its operands before rounding.
+const DIVISION_DIGITS: u32 = 18;
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt { negative: false, magnitude: Vec::new() }
+    }
+
+    pub fn from_i64(value: i64) -> BigInt {
+        let mut magnitude = value.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude != 0 {
+            limbs.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        BigInt::new(value < 0, limbs)
+    }
+
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> BigInt {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        BigInt { negative, magnitude }
+    }
+
+    /// Parses an optionally signed run of decimal digits.
+    pub fn parse(text: &str) -> Option<BigInt> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for chunk in digits.as_bytes().chunks(9) {
+            let value = chunk.iter().fold(0u32, |value, digit| value * 10 + (digit - b'0') as u32);
+            mul_small_add(&mut magnitude, 10u32.pow(chunk.len() as u32), value);
+        }
+        Some(BigInt::new(negative, magnitude))
+    }
+
+    /// Parses unsigned digits in base `radix`, as used by `0x`, `0b` and `0o` literals.
+    pub fn parse_radix(digits: &str, radix: u32) -> Option<BigInt> {
+        if digits.is_empty() {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for digit in digits.chars() {
+            mul_small_add(&mut magnitude, radix, digit.to_digit(radix)?);
+        }
+        Some(BigInt::new(false, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self.magnitude.iter().rev().fold(0u64, |value, limb| (value << 32) | *limb as u64);
+        match self.negative {
+            true if magnitude == i64::MIN.unsigned_abs() => Some(i64::MIN),
+            true => i64::try_from(magnitude).ok().map(|value| -value),
+            false => i64::try_from(magnitude).ok(),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.magnitude.iter().rev().fold(0f64, |value, limb| value * 4294967296.0 + *limb as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    pub fn neg(&self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::new(false, self.magnitude.clone())
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_magnitudes(&self.magnitude, &other.magnitude));
+        }
+        match compare_magnitudes(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::new(other.negative, sub_magnitudes(&other.magnitude, &self.magnitude)),
+            _ => BigInt::new(self.negative, sub_magnitudes(&self.magnitude, &other.magnitude)),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> BigInt {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &BigInt) -> BigInt {
+        let mut product = vec![0u32; self.magnitude.len() + other.magnitude.len()];
+        for (i, left) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, right) in other.magnitude.iter().enumerate() {
+                let value = product[i + j] as u64 + *left as u64 * *right as u64 + carry;
+                product[i + j] = value as u32;
+                carry = value >> 32;
+            }
+            product[i + other.magnitude.len()] = carry as u32;
+        }
+        BigInt::new(self.negative != other.negative, product)
+    }
+
+    /// Division truncating towards zero, the remainder takes the sign of the dividend.
+    /// Returns `None` for a zero divisor.
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitudes(&self.magnitude, &other.magnitude);
+        Some((BigInt::new(self.negative != other.negative, quotient), BigInt::new(self.negative, remainder)))
+    }
+
+    /// `None` when the result would take more than `MAX_BITS`.
+    pub fn pow(&self, exponent: u32) -> Option<BigInt> {
+        // Every factor adds at least `bit_length - 1` bits
+        if self.bit_length().saturating_sub(1) * exponent as u64 > MAX_BITS {
+            return None;
+        }
+        Some(self.power(exponent))
+    }
+
+    fn power(&self, mut exponent: u32) -> BigInt {
+        let mut result = BigInt::from_i64(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+
+    /// Applies a bitwise operator limb by limb on the two's complement form, as if
+    /// both operands were sign extended to infinitely many bits.
+    pub fn bitwise(&self, other: &BigInt, operator: fn(u32, u32) -> u32) -> BigInt {
+        let length = self.magnitude.len().max(other.magnitude.len()) + 1;
+        let left = self.twos_complement(length);
+        let right = other.twos_complement(length);
+        let limbs: Vec<u32> = left.iter().zip(right.iter()).map(|(l, r)| operator(*l, *r)).collect();
+        BigInt::from_twos_complement(limbs)
+    }
+
+    /// `!x`, which is `-x - 1` in two's complement.
+    pub fn not(&self) -> BigInt {
+        self.neg().sub(&BigInt::from_i64(1))
+    }
+
+    /// `None` when the result would take more than `MAX_BITS`.
+    pub fn shl(&self, shift: u32) -> Option<BigInt> {
+        if self.is_zero() {
+            return Some(BigInt::zero());
+        }
+        if self.bit_length() + shift as u64 > MAX_BITS {
+            return None;
+        }
+        let mut magnitude = vec![0u32; (shift / 32) as usize];
+        magnitude.extend_from_slice(&self.magnitude);
+        mul_small_add(&mut magnitude, 1 << (shift % 32), 0);
+        Some(BigInt::new(self.negative, magnitude))
+    }
+
+    /// Whether the value fits a two's complement integer of `bits` bits, like an `i32` for 32.
+    pub fn fits_signed(&self, bits: u32) -> bool {
+        // The most negative value of a width has one more unit of magnitude than the most positive
+        let magnitude = match self.negative {
+            true => self.abs().sub(&BigInt::from_i64(1)),
+            false => self.clone(),
+        };
+        magnitude.bit_length() < bits as u64
+    }
+
+    /// Bits needed for the magnitude, `0` for zero.
+    fn bit_length(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(last) => self.magnitude.len() as u64 * 32 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// An arithmetic shift, rounding towards negative infinity like shifting the two's complement would.
+    pub fn shr(&self, shift: u32) -> BigInt {
+        let limbs = (shift / 32) as usize;
+        if limbs >= self.magnitude.len() {
+            return if self.negative { BigInt::from_i64(-1) } else { BigInt::zero() };
+        }
+        let mut magnitude = self.magnitude[limbs..].to_vec();
+        let dropped = self.magnitude[..limbs].iter().any(|limb| *limb != 0);
+        let remainder = div_rem_small(&mut magnitude, 1 << (shift % 32));
+        let quotient = BigInt::new(self.negative, magnitude);
+        if self.negative && (dropped || remainder != 0) {
+            quotient.sub(&BigInt::from_i64(1))
+        } else {
+            quotient
+        }
+    }
+
+    fn twos_complement(&self, length: usize) -> Vec<u32> {
+        let mut limbs = self.magnitude.clone();
+        limbs.resize(length, 0);
+        if self.negative {
+            negate_limbs(&mut limbs);
+        }
+        limbs
+    }
+
+    fn from_twos_complement(mut limbs: Vec<u32>) -> BigInt {
+        let negative = limbs.last().is_some_and(|limb| limb >> 31 == 1);
+        if negative {
+            negate_limbs(&mut limbs);
+        }
+        BigInt::new(negative, limbs)
+    }
+
+    fn pow10(exponent: u32) -> BigInt {
+        BigInt::from_i64(10).power(exponent)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off base 10^9 chunks, least significant first
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, 1_000_000_000));
+        }
+        if self.negative { write!(f, "-")? }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() { write!(f, "{}", first)? }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decimal {
+    pub fn new(unscaled: BigInt, scale: u32) -> Decimal {
+        Decimal { unscaled, scale }
+    }
+
+    pub fn from_bigint(value: BigInt) -> Decimal {
+        Decimal::new(value, 0)
+    }
+
+    /// Parses `digits[.digits]` with an optional sign, the number of fractional digits sets the scale.
+    pub fn parse(text: &str) -> Option<Decimal> {
+        match text.split_once('.') {
+            Some((whole, fraction)) => {
+                if fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                let unscaled = BigInt::parse(&format!("{}{}", whole, fraction))?;
+                Some(Decimal::new(unscaled, fraction.len() as u32))
+            }
+            None => BigInt::parse(text).map(Decimal::from_bigint),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.unscaled.is_zero()
+    }
+
+    /// The unscaled value at a scale at least as large as the current one.
+    fn rescaled(&self, scale: u32) -> BigInt {
+        self.unscaled.mul(&BigInt::pow10(scale - self.scale))
+    }
+
+    pub fn neg(&self) -> Decimal {
+        Decimal::new(self.unscaled.neg(), self.scale)
+    }
+
+    pub fn add(&self, other: &Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        Decimal::new(self.rescaled(scale).add(&other.rescaled(scale)), scale)
+    }
+
+    pub fn sub(&self, other: &Decimal) -> Decimal {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Decimal) -> Decimal {
+        Decimal::new(self.unscaled.mul(&other.unscaled), self.scale + other.scale)
+    }
+
+    /// Exact when the quotient terminates within `DIVISION_DIGITS` extra digits,
+    /// otherwise rounded half away from zero. Returns `None` for a zero divisor.
+    pub fn div(&self, other: &Decimal) -> Option<Decimal> {
+        if other.is_zero() {
+            return None;
+        }
+        let minimum_scale = self.scale.max(other.scale);
+        let scale = minimum_scale + DIVISION_DIGITS;
+        // (a / 10^sa) / (b / 10^sb) * 10^scale = a * 10^(scale + sb - sa) / b
+        let numerator = self.unscaled.mul(&BigInt::pow10(scale + other.scale - self.scale));
+        let (mut quotient, remainder) = numerator.div_rem(&other.unscaled)?;
+        if remainder.abs().mul(&BigInt::from_i64(2)) >= other.unscaled.abs() {
+            let step = if self.unscaled.is_negative() != other.unscaled.is_negative() { -1 } else { 1 };
+            quotient = quotient.add(&BigInt::from_i64(step));
+        }
+        Some(Decimal::new(quotient, scale).trimmed(minimum_scale))
+    }
+
+    /// The remainder of a division truncated towards zero, it takes the sign of the dividend.
+    pub fn rem(&self, other: &Decimal) -> Option<Decimal> {
+        let scale = self.scale.max(other.scale);
+        let (_, remainder) = self.rescaled(scale).div_rem(&other.rescaled(scale))?;
+        Some(Decimal::new(remainder, scale))
+    }
+
+    /// `None` when the digits would take more than `MAX_BITS`, or the scale a power of ten that large.
+    pub fn pow(&self, exponent: u32) -> Option<Decimal> {
+        // `10^scale` takes a bit over `3.3 * scale` bits
+        let scale = self.scale.checked_mul(exponent).filter(|scale| *scale as u64 * 4 <= MAX_BITS)?;
+        Some(Decimal::new(self.unscaled.pow(exponent)?, scale))
+    }
+
+    /// Drops trailing fractional zeros, keeping at least `minimum_scale` digits.
+    fn trimmed(mut self, minimum_scale: u32) -> Decimal {
+        let ten = BigInt::from_i64(10);
+        while self.scale > minimum_scale {
+            match self.unscaled.div_rem(&ten) {
+                Some((quotient, remainder)) if remainder.is_zero() => {
+                    self.unscaled = quotient;
+                    self.scale -= 1;
+                }
+                _ => break,
+            }
+        }
+        self
+    }
+
+    /// The integer part, truncated towards zero.
+    pub fn truncate(&self) -> BigInt {
+        match self.unscaled.div_rem(&BigInt::pow10(self.scale)) {
+            Some((quotient, _)) => quotient,
+            None => BigInt::zero(),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescaled(scale).cmp(&other.rescaled(scale))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let digits = self.unscaled.abs().to_string();
+        if self.unscaled.is_negative() { write!(f, "-")? }
+        if self.scale == 0 {
+            return write!(f, "{}", digits);
+        }
+        // Padded by hand, a formatting width can't exceed `u16::MAX`
+        let padding = (self.scale as usize + 1).saturating_sub(digits.len());
+        let digits = format!("{}{}", "0".repeat(padding), digits);
+        let (whole, fraction) = digits.split_at(digits.len() - self.scale as usize);
+        write!(f, "{}.{}", whole, fraction)
+    }
+}
+
+fn compare_magnitudes(left: &[u32], right: &[u32]) -> Ordering {
+    left.len().cmp(&right.len()).then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitudes(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..left.len().max(right.len()) {
+        let value = *left.get(i).unwrap_or(&0) as u64 + *right.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push(value as u32);
+        carry = value >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+/// `left - right`, where `left` is at least as large as `right`.
+fn sub_magnitudes(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (i, limb) in left.iter().enumerate() {
+        let mut value = *limb as i64 - *right.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if value < 0 {
+            value += 1 << 32;
+            borrow = 1;
+        }
+        difference.push(value as u32);
+    }
+    difference
+}
+
+fn mul_small_add(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in magnitude.iter_mut() {
+        let value = *limb as u64 * factor as u64 + carry;
+        *limb = value as u32;
+        carry = value >> 32;
+    }
+    if carry != 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+/// Negates fixed-width two's complement limbs in place, inverting and adding one.
+fn negate_limbs(limbs: &mut [u32]) {
+    let mut carry = true;
+    for limb in limbs.iter_mut() {
+        let (value, overflow) = (!*limb).overflowing_add(carry as u32);
+        *limb = value;
+        carry = overflow;
+    }
+}
+
+/// Divides `magnitude` in place by a single limb and returns the remainder.
+fn div_rem_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in magnitude.iter_mut().rev() {
+        let value = (remainder << 32) | *limb as u64;
+        *limb = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    remainder as u32
+}
+
+/// Schoolbook binary long division, simple rather than fast.
+fn div_rem_magnitudes(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [single] = divisor {
+        let mut quotient = dividend.to_vec();
+        let remainder = div_rem_small(&mut quotient, *single);
+        return (quotient, vec![remainder]);
+    }
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..dividend.len() * 32).rev() {
+        // remainder = remainder * 2 + next bit of the dividend
+        let incoming = (dividend[bit / 32] >> (bit % 32)) & 1;
+        mul_small_add(&mut remainder, 2, incoming);
+        if compare_magnitudes(&remainder, divisor) != Ordering::Less {
+            remainder = sub_magnitudes(&remainder, divisor);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(text: &str) -> BigInt {
+        BigInt::parse(text).unwrap()
+    }
+
+    fn dec(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    fn div_rem(left: &str, right: &str) -> (String, String) {
+        let (quotient, remainder) = int(left).div_rem(&int(right)).unwrap();
+        (quotient.to_string(), remainder.to_string())
+    }
+
+    #[test]
+    fn div_rem_truncates_towards_zero() {
+        assert_eq!(div_rem("7", "2"), ("3".to_string(), "1".to_string()));
+        assert_eq!(div_rem("-7", "2"), ("-3".to_string(), "-1".to_string()));
+        assert_eq!(div_rem("7", "-2"), ("-3".to_string(), "1".to_string()));
+        assert_eq!(div_rem("-7", "-2"), ("3".to_string(), "-1".to_string()));
+    }
+
+    #[test]
+    fn div_rem_of_multi_limb_numbers() {
+        let dividend = "340282366920938463463374607431768211457";
+        assert_eq!(div_rem(dividend, "18446744073709551616"), ("18446744073709551616".to_string(), "1".to_string()));
+        assert_eq!(div_rem(dividend, "12345678901234567890"), ("27562871968661863283".to_string(), "7065756055006428587".to_string()));
+        assert_eq!(div_rem("5", "12345678901234567890"), ("0".to_string(), "5".to_string()));
+        assert!(int("1").div_rem(&BigInt::zero()).is_none());
+    }
+
+    #[test]
+    fn shr_floors_negative_numbers() {
+        assert_eq!(int("-8").shr(1).to_string(), "-4");
+        assert_eq!(int("-7").shr(1).to_string(), "-4");
+        assert_eq!(int("-1").shr(40).to_string(), "-1");
+        assert_eq!(int("-18446744073709551617").shr(64).to_string(), "-2");
+        assert_eq!(int("-18446744073709551616").shr(64).to_string(), "-1");
+        assert_eq!(int("7").shr(1).to_string(), "3");
+        assert_eq!(int("7").shr(100).to_string(), "0");
+    }
+
+    #[test]
+    fn bitwise_uses_twos_complement() {
+        let and = |l: &str, r: &str| int(l).bitwise(&int(r), |l, r| l & r).to_string();
+        let or = |l: &str, r: &str| int(l).bitwise(&int(r), |l, r| l | r).to_string();
+        let xor = |l: &str, r: &str| int(l).bitwise(&int(r), |l, r| l ^ r).to_string();
+        assert_eq!(and("12", "10"), "8");
+        assert_eq!(or("12", "10"), "14");
+        assert_eq!(xor("12", "10"), "6");
+        assert_eq!(and("-1", "18446744073709551616"), "18446744073709551616");
+        assert_eq!(and("-4", "7"), "4");
+        assert_eq!(or("-4", "1"), "-3");
+        assert_eq!(xor("-1", "5"), "-6");
+        assert_eq!(int("5").not().to_string(), "-6");
+        assert_eq!(int("-18446744073709551617").not().to_string(), "18446744073709551616");
+    }
+
+    #[test]
+    fn shl_respects_the_bit_budget() {
+        assert_eq!(int("1").shl(100).unwrap().to_string(), "1267650600228229401496703205376");
+        assert_eq!(int("-3").shl(2).unwrap().to_string(), "-12");
+        assert!(int("1").shl(u32::MAX).is_none());
+        assert!(BigInt::zero().shl(u32::MAX).is_some());
+    }
+
+    #[test]
+    fn pow_respects_the_bit_budget() {
+        assert_eq!(int("2").pow(100).unwrap().to_string(), "1267650600228229401496703205376");
+        assert_eq!(int("-1").pow(u32::MAX).unwrap().to_string(), "-1");
+        assert!(int("3").pow(u32::MAX).is_none());
+        assert!(dec("0.5").pow(3).is_some_and(|power| power.to_string() == "0.125"));
+        assert!(dec("1.5").pow(u32::MAX).is_none());
+    }
+
+    #[test]
+    fn fits_signed_uses_the_twos_complement_range() {
+        assert!(int("32767").fits_signed(16));
+        assert!(!int("32768").fits_signed(16));
+        assert!(int("-32768").fits_signed(16));
+        assert!(!int("-32769").fits_signed(16));
+        assert!(BigInt::zero().fits_signed(1));
+        assert!(int("-170141183460469231731687303715884105728").fits_signed(128));
+        assert!(!int("170141183460469231731687303715884105728").fits_signed(128));
+    }
+
+    #[test]
+    fn decimal_division_rounds_half_away_from_zero() {
+        assert_eq!(dec("1").div(&dec("4")).unwrap().to_string(), "0.25");
+        assert_eq!(dec("1").div(&dec("3")).unwrap().to_string(), "0.333333333333333333");
+        assert_eq!(dec("2").div(&dec("3")).unwrap().to_string(), "0.666666666666666667");
+        assert_eq!(dec("-2").div(&dec("3")).unwrap().to_string(), "-0.666666666666666667");
+        assert_eq!(dec("1.10").div(&dec("2")).unwrap().to_string(), "0.55");
+        assert_eq!(dec("3.00").div(&dec("1.5")).unwrap().to_string(), "2.00");
+        assert!(dec("1").div(&dec("0.0")).is_none());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(BigInt::zero().to_string(), "0");
+        assert_eq!(int("-0").to_string(), "0");
+        assert_eq!(int("1000000000").to_string(), "1000000000");
+        assert_eq!(int("-123456789012345678901234567890").to_string(), "-123456789012345678901234567890");
+        assert_eq!(dec("1.10").to_string(), "1.10");
+        assert_eq!(dec("-0.05").to_string(), "-0.05");
+        assert_eq!(dec("0.001").to_string(), "0.001");
+        assert_eq!(dec("12").to_string(), "12");
+        assert_eq!(dec("1.10").add(&dec("2")).to_string(), "3.10");
+    }
+}
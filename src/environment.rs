@@ -0,0 +1,50 @@
+use crate::errors::{ErrorTypes, LunalaErrors};
+use crate::object::Object;
+use crate::tokens::Token;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub struct Environment {
+    values: HashMap<String, Object>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment { values: HashMap::new(), enclosing: None }
+    }
+
+    /// A nested scope, lookups that miss here continue in `enclosing`.
+    pub fn new_enclosed(enclosing: Rc<RefCell<Environment>>) -> Environment {
+        Environment { values: HashMap::new(), enclosing: Some(enclosing) }
+    }
+
+    /// Binds `name` to `value` in this scope, shadowing any binding of an enclosing scope.
+    /// A redeclaration in the same scope replaces the previous binding.
+    pub fn define(&mut self, name: &str, value: Object) {
+        self.values.insert(name.to_owned(), value);
+    }
+
+    /// Rebinds `name` in the innermost scope declaring it, assigning to an undeclared name is an error.
+    pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), LunalaErrors> {
+        if let Some(slot) = self.values.get_mut(&name.access_lexeme()) {
+            *slot = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(LunalaErrors::new(ErrorTypes::UndefinedVariable(name.access_lexeme()), name.line()))
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Object, LunalaErrors> {
+        if let Some(value) = self.values.get(&name.access_lexeme()) {
+            return Ok(value.clone());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().get(name),
+            None => Err(LunalaErrors::new(ErrorTypes::UndefinedVariable(name.access_lexeme()), name.line()))
+        }
+    }
+}
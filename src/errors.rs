@@ -1,27 +1,75 @@
 use crate::tokens::Token;
-use crate::expressions::ExpType;
-use std::num::ParseFloatError;
+use crate::expressions::{ExpType, IntegerPrecision};
 
 pub struct LunalaErrors {
-    e_type: ErrorTypes,
+    // Boxed to keep `Result<_, LunalaErrors>` small, some variants carry whole expressions
+    e_type: Box<ErrorTypes>,
     line: usize,
 }
 
 impl LunalaErrors {
     pub fn new(e_type: ErrorTypes, line: usize) -> LunalaErrors {
-        LunalaErrors { e_type, line }
+        LunalaErrors { e_type: Box::new(e_type), line }
+    }
+
+    /// Fills in `line` for errors raised without one, e.g. by value conversions.
+    pub fn or_line(self, line: usize) -> LunalaErrors {
+        match self.line {
+            0 => LunalaErrors { line, ..self },
+            _ => self,
+        }
     }
 }
 
 pub enum ErrorTypes {
     InvalidToken(String),
     UnterminatedString,
+    UnterminatedTemplate,
+    InvalidEscape(String),
+    InvalidCharLiteral(String),
+    MalformedNumber(String, String),
     NoPreviousItem(usize),
-    ErrorNotANumber(String, Option<ParseFloatError>),
+    ErrorNotANumber(String, Option<String>),
     Error(String),
     ExpressionExpected(String),
     NotABooleanValue(String),
     InvalidUnaryExpression(Token, ExpType),
+    UndefinedVariable(String),
+    InvalidAssignmentTarget(String),
+    NonBooleanCondition(String, String),
+    JumpOutsideLoop(String),
+    ReturnOutsideFunction,
+    NotCallable(String),
+    ArityMismatch(String, usize, usize),
+    ArithmeticError(String),
+    TypeMismatch(String, String, String),
+    UnsupportedOperand(String, String),
+    InvalidArgument(String, String, String),
+    NotIndexable(String),
+    InvalidIndex(String),
+    IndexOutOfBounds(IntegerPrecision, usize),
+    InvalidMapKey(String),
+    MissingKey(String),
+    NotAssignable(String),
+    PatternMismatch(String, String),
+    NotAStruct(String),
+    UnknownField(String, String),
+    DuplicateField(String, String),
+    MissingField(String, String),
+    NoFields(String),
+    UndefinedProperty(String, String),
+    SelfOutsideMethod,
+    SuperOutsideSubclass,
+    InvalidSuperclass(String, String),
+    DuplicateVariant(String, String),
+    UnknownVariant(String, String),
+    NotAnEnum(String),
+    NoMatchingArm(String),
+    NilOperand(String, String),
+    CallDepthExceeded(usize),
+    NestingTooDeep(usize),
+    StringTooLong(usize),
+    NilReceiver(String, String),
 }
 
 impl ErrorTypes {
@@ -29,6 +77,14 @@ impl ErrorTypes {
         match self {
             ErrorTypes::InvalidToken(token) => { format!("Invalid token: `{}`", token) }
             ErrorTypes::UnterminatedString => { "Unterminated string".to_string() }
+            ErrorTypes::UnterminatedTemplate => { "Unterminated template literal".to_string() }
+            ErrorTypes::InvalidEscape(sequence) => { format!("Invalid escape sequence `{}`", sequence) }
+            ErrorTypes::InvalidCharLiteral(literal) => {
+                format!("Invalid character literal `{}`, it must hold exactly one character", literal)
+            }
+            ErrorTypes::MalformedNumber(literal, reason) => {
+                format!("Malformed number literal `{}`: {}", literal, reason)
+            }
             ErrorTypes::NoPreviousItem(location) => { format!("No previous item found at location: {}", location) }
             ErrorTypes::ErrorNotANumber(token, err) => {
                 let err_msg = match err {
@@ -47,6 +103,110 @@ impl ErrorTypes {
             ErrorTypes::InvalidUnaryExpression(operator, exp) => {
                 format!("Invalid unary expression: [{}, {}]", operator, exp)
             }
+            ErrorTypes::UndefinedVariable(name) => {
+                format!("Undefined variable `{}`", name)
+            }
+            ErrorTypes::InvalidAssignmentTarget(target) => {
+                format!("Invalid assignment target `{}`", target)
+            }
+            ErrorTypes::NonBooleanCondition(keyword, val) => {
+                format!("The condition of `{}` must be a boolean, found `{}`", keyword, val)
+            }
+            ErrorTypes::JumpOutsideLoop(keyword) => {
+                format!("`{}` can only be used inside a loop", keyword)
+            }
+            ErrorTypes::ReturnOutsideFunction => { "`return` can only be used inside a function".to_string() }
+            ErrorTypes::NotCallable(callee) => {
+                format!("`{}` is not callable", callee)
+            }
+            ErrorTypes::ArityMismatch(name, expected, found) => {
+                format!("`{}` expects {} argument(s), got {}", name, expected, found)
+            }
+            ErrorTypes::ArithmeticError(reason) => {
+                format!("Arithmetic error: {}", reason)
+            }
+            ErrorTypes::TypeMismatch(operator, left, right) => {
+                format!("Unsupported operand types for `{}`: {} and {}", operator, left, right)
+            }
+            ErrorTypes::UnsupportedOperand(operator, operand) => {
+                format!("Unsupported operand type for `{}`: {}", operator, operand)
+            }
+            ErrorTypes::InvalidArgument(function, expected, found) => {
+                format!("`{}` expects {}, got {}", function, expected, found)
+            }
+            ErrorTypes::NotIndexable(value) => {
+                format!("{} cannot be indexed", value)
+            }
+            ErrorTypes::InvalidIndex(index) => {
+                format!("An index must be an integer, found {}", index)
+            }
+            ErrorTypes::IndexOutOfBounds(index, length) => {
+                format!("Index {} is out of bounds for length {}", index, length)
+            }
+            ErrorTypes::InvalidMapKey(key) => {
+                format!("{} cannot be used as a map key, keys are strings, chars, integers or booleans", key)
+            }
+            ErrorTypes::MissingKey(key) => {
+                format!("Key {} is not in the map", key)
+            }
+            ErrorTypes::NotAssignable(value) => {
+                format!("The elements of {} cannot be assigned", value)
+            }
+            ErrorTypes::PatternMismatch(pattern, value) => {
+                format!("Cannot destructure {} with the pattern `{}`", value, pattern)
+            }
+            ErrorTypes::NotAStruct(name) => {
+                format!("`{}` is not a struct", name)
+            }
+            ErrorTypes::UnknownField(name, field) => {
+                format!("`{}` has no field `{}`", name, field)
+            }
+            ErrorTypes::DuplicateField(name, field) => {
+                format!("Field `{}` of `{}` is given more than once", field, name)
+            }
+            ErrorTypes::MissingField(name, field) => {
+                format!("Missing field `{}` in `{}`", field, name)
+            }
+            ErrorTypes::NoFields(value) => {
+                format!("{} has no fields", value)
+            }
+            ErrorTypes::UndefinedProperty(class, name) => {
+                format!("`{}` has no field or method `{}`", class, name)
+            }
+            ErrorTypes::SelfOutsideMethod => { "`self` can only be used inside a method".to_string() }
+            ErrorTypes::SuperOutsideSubclass => {
+                "`super` can only be used inside a method of a class with a superclass".to_string()
+            }
+            ErrorTypes::InvalidSuperclass(class, superclass) => {
+                format!("`{}` cannot inherit from `{}`, it is not a class", class, superclass)
+            }
+            ErrorTypes::DuplicateVariant(name, variant) => {
+                format!("Variant `{}` of `{}` is declared more than once", variant, name)
+            }
+            ErrorTypes::UnknownVariant(name, variant) => {
+                format!("`{}` has no variant `{}`", name, variant)
+            }
+            ErrorTypes::NotAnEnum(name) => {
+                format!("`{}` is not an enum", name)
+            }
+            ErrorTypes::NoMatchingArm(value) => {
+                format!("No `match` arm matches {}", value)
+            }
+            ErrorTypes::NilOperand(expression, operator) => {
+                format!("`{}` is nil and can't be used with `{}`", expression, operator)
+            }
+            ErrorTypes::NilReceiver(expression, field) => {
+                format!("`{}` is nil, so it has no field `{}`", expression, field)
+            }
+            ErrorTypes::CallDepthExceeded(limit) => {
+                format!("Calls nested more than {} deep, is the recursion missing a base case?", limit)
+            }
+            ErrorTypes::NestingTooDeep(limit) => {
+                format!("Source nested more than {} deep", limit)
+            }
+            ErrorTypes::StringTooLong(limit) => {
+                format!("The string would be longer than {} bytes", limit)
+            }
         }.to_owned()
     }
 }
@@ -1,12 +1,18 @@
+use crate::bignum::{BigInt, Decimal};
 use crate::errors::{ErrorTypes, LunalaErrors};
-use crate::tokens::Token;
+use crate::pattern::MatchArm;
+use crate::statement::FunctionStatement;
+use crate::tokens::{Token, TokenType};
+use std::rc::Rc;
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct Expression {
     expression_type: ExpType,
 }
 
 pub type Precision = f64;
+pub type IntegerPrecision = i64;
 
 #[derive(Debug, Clone)]
 pub enum ExpType {
@@ -20,31 +26,114 @@ pub enum ExpType {
         left: Box<ExpType>,
         right: Box<ExpType>,
     },
+    Logical {
+        operator: Token,
+        left: Box<ExpType>,
+        right: Box<ExpType>,
+    },
     Grouping {
         expression: Box<ExpType>,
-    }
+    },
+    Variable {
+        name: Token,
+    },
+    Assign {
+        name: Token,
+        operator: Token,
+        value: Box<ExpType>,
+    },
+    Call {
+        callee: Box<ExpType>,
+        paren: Token,
+        arguments: Vec<ExpType>,
+    },
+    Function(Rc<FunctionStatement>),
+    /// A template literal, its parts are joined by their string form. `quote` is the closing backtick
+    Interpolation {
+        quote: Token,
+        parts: Vec<ExpType>,
+    },
+    List {
+        elements: Vec<ExpType>,
+    },
+    Tuple {
+        elements: Vec<ExpType>,
+    },
+    /// A map literal, its entries are kept in the order they are written
+    Map {
+        brace: Token,
+        entries: Vec<(ExpType, ExpType)>,
+    },
+    /// Creates an instance of a struct, `Point { x: 1, y: 2 }`
+    StructLiteral {
+        name: Token,
+        fields: Vec<(Token, ExpType)>,
+    },
+    Index {
+        object: Box<ExpType>,
+        bracket: Token,
+        index: Box<ExpType>,
+    },
+    IndexAssign {
+        object: Box<ExpType>,
+        bracket: Token,
+        index: Box<ExpType>,
+        operator: Token,
+        value: Box<ExpType>,
+    },
+    Get {
+        object: Box<ExpType>,
+        name: Token,
+    },
+    /// `object?.name`, skips the rest of the chain when the object is `nil`
+    OptionalGet {
+        object: Box<ExpType>,
+        name: Token,
+    },
+    /// The instance a method was called on
+    SelfValue {
+        keyword: Token,
+    },
+    /// A method of the superclass, bound to the current instance
+    Super {
+        keyword: Token,
+        method: Token,
+    },
+    Set {
+        object: Box<ExpType>,
+        name: Token,
+        operator: Token,
+        value: Box<ExpType>,
+    },
+    /// A `match` in expression position, the value of the first matching arm
+    Match {
+        keyword: Token,
+        subject: Box<ExpType>,
+        arms: Vec<MatchArm<ExpType>>,
+    },
+}
+
+/// The fixed integer widths a value can be checked against, `i32(x)` fails unless `x` fits.
+/// Arithmetic never wraps at a width, an `i64` result that overflows becomes a bigint instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegerWidth {
+    I16, I32, I64, I128,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
-    Number(Precision),
+    Integer(IntegerPrecision),
+    BigInt(BigInt),
+    Float(Precision),
+    Decimal(Decimal),
     Bool(bool),
     String(String),
+    Char(char),
+    Nil,
 }
 
-pub enum _Number {
-    Int(_Integer),
-    Float(_Float),
-}
-
-pub enum _Integer {
-    I16, I32, I64, I128,
-}
-
-pub enum _Float {
-    F16, F32, F64, F128,
-}
 
+#[allow(dead_code)]
 impl Expression {
     pub fn new(expression_type: ExpType) -> Expression {
         Expression { expression_type }
@@ -76,45 +165,160 @@ impl std::fmt::Display for Literal {
 impl ExpType {
     pub fn get_string(&self) -> String {
         match self {
+            // Quoted, so `m["a"]` doesn't read as `m[a]`
+            ExpType::Literal(Literal::String(value)) => {
+                format!("{:?}", value)
+            }
+            ExpType::Literal(Literal::Char(value)) => {
+                format!("{:?}", value)
+            }
             ExpType::Literal(val) => { 
                 val.to_string() 
             }
             ExpType::Unary { expression, operator } => {
-                format!("{}{}", operator.token_type(), expression.get_string())
+                format!("{}{}", operator.token_type().symbol(), expression.get_string())
             }
             ExpType::Binary { left, operator, right } => {
-                format!("{} {} {}", left, operator.token_type(), right)
+                format!("{} {} {}", left, operator.token_type().symbol(), right)
+            }
+            ExpType::Logical { left, operator, right } => {
+                format!("{} {} {}", left, operator.token_type().symbol(), right)
             }
             ExpType::Grouping { expression } => {
                 format!("({})", expression.get_string())
             }
+            ExpType::Variable { name } => {
+                name.access_lexeme()
+            }
+            ExpType::Assign { name, operator, value } => {
+                format!("{} {} {}", name.access_lexeme(), operator.token_type().symbol(), value)
+            }
+            ExpType::Call { callee, arguments, .. } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.get_string()).collect();
+                format!("{}({})", callee, arguments.join(", "))
+            }
+            ExpType::Function(declaration) => {
+                format!("fn {}", declaration)
+            }
+            ExpType::Interpolation { parts, .. } => {
+                let parts: Vec<String> = parts.iter().map(|part| match part {
+                    ExpType::Literal(Literal::String(text)) => text.clone(),
+                    _ => format!("${{{}}}", part),
+                }).collect();
+                format!("`{}`", parts.concat())
+            }
+            ExpType::List { elements } => {
+                let elements: Vec<String> = elements.iter().map(|element| element.get_string()).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            ExpType::Tuple { elements } => {
+                let elements: Vec<String> = elements.iter().map(|element| element.get_string()).collect();
+                match elements.len() {
+                    1 => format!("({},)", elements[0]),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            ExpType::Map { entries, .. } => {
+                let entries: Vec<String> = entries.iter().map(|(key, value)| format!("{}: {}", key, value)).collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            ExpType::StructLiteral { name, fields } => {
+                let fields: Vec<String> = fields.iter().map(|(field, value)| format!("{}: {}", field.access_lexeme(), value)).collect();
+                format!("{} {{ {} }}", name.access_lexeme(), fields.join(", "))
+            }
+            ExpType::Get { object, name } => {
+                format!("{}.{}", object, name.access_lexeme())
+            }
+            ExpType::OptionalGet { object, name } => {
+                format!("{}?.{}", object, name.access_lexeme())
+            }
+            ExpType::SelfValue { .. } => {
+                "self".to_string()
+            }
+            ExpType::Super { method, .. } => {
+                format!("super.{}", method.access_lexeme())
+            }
+            ExpType::Set { object, name, operator, value } => {
+                format!("{}.{} {} {}", object, name.access_lexeme(), operator.token_type().symbol(), value)
+            }
+            ExpType::Match { subject, arms, .. } => {
+                let arms: Vec<String> = arms.iter().map(|arm| arm.to_string()).collect();
+                format!("match {} {{ {} }}", subject, arms.join(", "))
+            }
+            ExpType::Index { object, index, .. } => {
+                format!("{}[{}]", object, index)
+            }
+            ExpType::IndexAssign { object, index, operator, value, .. } => {
+                format!("{}[{}] {} {}", object, index, operator.token_type().symbol(), value)
+            }
         }
     }
 }
 
 impl Literal {
     pub fn new_number(token: &Token) -> Result<Literal, LunalaErrors> {
-        let value = token.access_lexeme().parse::<Precision>();
-        match value {
-            Ok(value) => Ok(Literal::Number(value)),
-            Err(err) => Err(LunalaErrors::new(
-                ErrorTypes::ErrorNotANumber(token.to_string(), Some(err)), 0
-            ))
-        }
+        let lexeme = token.access_lexeme();
+        let value = match token.token_type() {
+            // Literals too large for an `i64` start out as a bigint
+            TokenType::Integer => match lexeme.parse::<IntegerPrecision>() {
+                Ok(integer) => Ok(Literal::Integer(integer)),
+                Err(_) => BigInt::parse(&lexeme).map(Literal::BigInt).ok_or_else(|| "not an integer".to_string()),
+            },
+            TokenType::Decimal => Decimal::parse(&lexeme).map(Literal::Decimal).ok_or_else(|| "not a decimal".to_string()),
+            _ => lexeme.parse::<Precision>().map(Literal::Float).map_err(|err| err.to_string()),
+        };
+        value.map_err(|err| LunalaErrors::new(
+            ErrorTypes::ErrorNotANumber(token.to_string(), Some(err)), token.line()
+        ))
     }
     
     fn get_string(&self) -> String {
         match self {
-            Literal::Number(value) => value.to_string(),
+            Literal::Integer(value) => value.to_string(),
+            Literal::BigInt(value) => value.to_string(),
+            Literal::Float(value) => format_float(*value),
+            Literal::Decimal(value) => format!("{}d", value),
             Literal::Bool(value) => value.to_string(),
             Literal::String(value) => value.to_string(),
+            Literal::Char(value) => value.to_string(),
+            Literal::Nil => "nil".to_string(),
         }
     }
-    
-    pub fn _get_number(&self) -> Result<Precision, LunalaErrors> {
+}
+
+impl IntegerWidth {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegerWidth::I16 => "i16",
+            IntegerWidth::I32 => "i32",
+            IntegerWidth::I64 => "i64",
+            IntegerWidth::I128 => "i128",
+        }
+    }
+
+    pub fn bits(&self) -> u32 {
         match self {
-            Literal::Number(value) => Ok(*value),
-            _ => Err(LunalaErrors::new(ErrorTypes::ErrorNotANumber(self.get_string(), None), 0))
+            IntegerWidth::I16 => 16,
+            IntegerWidth::I32 => 32,
+            IntegerWidth::I64 => 64,
+            IntegerWidth::I128 => 128,
         }
     }
+
+    pub fn contains(&self, value: &BigInt) -> bool {
+        value.fits_signed(self.bits())
+    }
+}
+
+/// Prints floats with a fractional part, so `3.0` stays distinguishable from the integer `3`.
+/// Very large and very small magnitudes use scientific notation, like `6.02e23`.
+pub fn format_float(value: Precision) -> String {
+    let magnitude = value.abs();
+    if value.is_finite() && (magnitude >= 1e16 || (magnitude != 0.0 && magnitude < 1e-5)) {
+        format!("{:e}", value)
+    } else if value.is_finite() && value.fract() == 0.0 {
+        format!("{:.1}", value)
+    } else {
+        value.to_string()
+    }
 }
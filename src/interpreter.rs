@@ -1,31 +1,49 @@
+use crate::bignum::{BigInt, Decimal, MAX_BITS};
+use crate::environment::Environment;
 use crate::errors::{ErrorTypes, LunalaErrors};
-use crate::expressions::{ExpType, Literal};
+use crate::expressions::{format_float, ExpType, IntegerPrecision, Precision};
+use crate::natives;
+use crate::object::{BoundMethod, Class, Enum, Function, Instance, InstanceOf, Map, Object, Struct, Variant, INITIALIZER};
+use crate::pattern::{MatchArm, Pattern};
 use crate::statement;
 use crate::statement::{Statement, StatementTrait, Statements};
-use crate::tokens::TokenType;
+use crate::tokens::{Token, TokenType};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
 
-pub struct Interpreter {}
+pub struct Interpreter {
+    environment: Rc<RefCell<Environment>>,
+    // Function calls currently running, bounded by `MAX_CALL_DEPTH`
+    depth: usize,
+}
 
-pub type Object = Literal;
+/// The longest string, in bytes, that repeating, concatenating or interpolating strings may produce.
+const MAX_STRING_LENGTH: usize = 1 << 28;
 
-impl Object {
-    fn from_literal(literal: Literal) -> Object {
-        match literal {
-            Literal::Number(number) => {
-                Object::Number(number)
-            }
-            Literal::Bool(bool) => {
-                Object::Bool(bool)
-            }
-            Literal::String(string) => {
-                Object::String(string)
-            }
-        }
-    }
+/// How deep calls may nest before a runaway recursion is reported instead of overflowing the stack.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// The names methods see their instance and superclass under.
+const SELF: &str = "self";
+const SUPER: &str = "super";
+
+/// How control continues after a statement has been executed.
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Object),
 }
 
 impl Interpreter {
-    fn visit_expression(expression: &ExpType) -> Result<Object, LunalaErrors> {
+    pub fn new() -> Interpreter {
+        let mut globals = Environment::new();
+        natives::define_natives(&mut globals);
+        Interpreter { environment: Rc::new(RefCell::new(globals)), depth: 0 }
+    }
+
+    fn visit_expression(&mut self, expression: &ExpType) -> Result<Object, LunalaErrors> {
         match expression {
             ExpType::Literal(literal) => {
                 //println!("Literal: [{}]", literal);
@@ -33,112 +51,1347 @@ impl Interpreter {
             }
             ExpType::Unary { operator, expression } => {
                 //println!("Unary: [{}, {}]", operator, expression);
-                match (operator.token_type(), *expression.clone()) {
-                    (TokenType::Minus, ExpType::Literal(literal)) => {
-                        let number = - literal._get_number()?;
-                        Ok(Object::Number(number))
+                let right = self.visit_expression(expression)?;
+                if matches!(right, Object::Nil) && operator.token_type() != TokenType::Bang {
+                    return Err(Self::nil_operand(operator.token_type(), operator.line(), expression));
+                }
+                let result = match operator.token_type() {
+                    TokenType::Minus => {
+                        match right {
+                            Object::Integer(integer) => Ok(match integer.checked_neg() {
+                                Some(negated) => Object::Integer(negated),
+                                None => Object::from_bigint(BigInt::from_i64(integer).neg()),
+                            }),
+                            Object::BigInt(integer) => Ok(Object::from_bigint(integer.neg())),
+                            Object::Decimal(decimal) => Ok(Object::Decimal(decimal.neg())),
+                            Object::Float(float) => Ok(Object::Float(-float)),
+                            _ => Err(LunalaErrors::new(ErrorTypes::UnsupportedOperand(
+                                operator.token_type().symbol(), right.describe()
+                            ), 0)),
+                        }
                     },
-                    (TokenType::Bang, exp) => {
-                        let bool = ! Self::truthy(exp.clone())?;
-                        Ok(Object::Bool(bool))
+                    TokenType::Bang => {
+                        Self::truthy(&right).map(|bool| Object::Bool(!bool))
+                    }
+                    TokenType::Tilde => {
+                        match right {
+                            Object::Integer(integer) => Ok(Object::Integer(!integer)),
+                            Object::BigInt(integer) => Ok(Object::from_bigint(integer.not())),
+                            _ => Err(LunalaErrors::new(ErrorTypes::UnsupportedOperand(
+                                operator.token_type().symbol(), right.describe()
+                            ), 0)),
+                        }
                     }
                     _ => Err(LunalaErrors::new(ErrorTypes::InvalidUnaryExpression(operator.clone(), *expression.clone()), 0))
-                }
+                };
+                result.map_err(|err| err.or_line(operator.line()))
 
             }
             ExpType::Binary { operator, left, right } => {
                 //println!("Binary: [{}, {}, {}]", left, operator, right);
                 
-                let left = Self::visit_expression(left)?;
-                let right = Self::visit_expression(right)?;
-                
-                match operator.token_type() {
-                    TokenType::Plus => {
-                        Ok(Object::Number(left._get_number()? + right._get_number()?))
-                    },
-                    TokenType::Minus => {
-                        Ok(Object::Number(left._get_number()? - right._get_number()?))
-                    },
-                    TokenType::Slash => {
-                        Ok(Object::Number(left._get_number()? / right._get_number()?))
-                    },
-                    TokenType::Star => {
-                        Ok(Object::Number(left._get_number()? * right._get_number()?))
-                    },
-                    TokenType::GreaterThan => {
-                        Ok(Object::Bool(left._get_number()? > right._get_number()?))
-                    },
-                    TokenType::GreaterEquals => {
-                        Ok(Object::Bool(left._get_number()? >= right._get_number()?))
-                    },
-                    TokenType::LessThan => {
-                        Ok(Object::Bool(left._get_number()? < right._get_number()?))
-                    },
-                    TokenType::LessEquals => {
-                        Ok(Object::Bool(left._get_number()? <= right._get_number()?))
-                    },
-                    TokenType::BangEquals => {
-                        Ok(Object::Bool(! Self::equal(&left, &right)))
-                    },
-                    TokenType::DoubleEquals => {
-                        Ok(Object::Bool( Self::equal(&left, &right)))
-                    },
-                    _ => Err(LunalaErrors::new(ErrorTypes::Error("Not a binary operator".to_owned()), 0))
+                let left_value = self.visit_expression(left)?;
+                let right_value = self.visit_expression(right)?;
+                Self::operate(operator.token_type(), operator.line(), (left, &left_value), (right, &right_value))
+            }
+            ExpType::Logical { operator, left, right } if operator.token_type() == TokenType::DoubleQuestion => {
+                match self.visit_expression(left)? {
+                    Object::Nil => self.visit_expression(right),
+                    value => Ok(value),
                 }
             }
+            ExpType::Logical { operator, left, right } => {
+                let left = self.visit_expression(left)?;
+                let left = Self::truthy(&left).map_err(|err| err.or_line(operator.line()))?;
+                // Only evaluate the right side when it can still change the outcome
+                let short_circuit = match operator.token_type() {
+                    TokenType::Or => left,
+                    _ => !left,
+                };
+                if short_circuit {
+                    return Ok(Object::Bool(left));
+                }
+                let right = self.visit_expression(right)?;
+                let right = Self::truthy(&right).map_err(|err| err.or_line(operator.line()))?;
+                Ok(Object::Bool(right))
+            }
             ExpType::Grouping { expression } => {
                 //println!("Group: {}", expression);
-                Self::visit_expression(expression)
+                self.visit_expression(expression)
+            }
+            ExpType::Variable { name } => {
+                self.environment.borrow().get(name)
+            }
+            ExpType::Assign { name, operator, value: expression } => {
+                // The target is read before the value is evaluated, so side effects happen in source order
+                let value = match operator.token_type().compound_operator() {
+                    Some(binary_operator) => {
+                        let current = self.environment.borrow().get(name)?;
+                        let value = self.visit_expression(expression)?;
+                        let target = ExpType::Variable { name: name.clone() };
+                        Self::operate(binary_operator, operator.line(), (&target, &current), (expression, &value))?
+                    }
+                    None => self.visit_expression(expression)?,
+                };
+                self.environment.borrow_mut().assign(name, value.clone())?;
+                Ok(value)
+            }
+            ExpType::Call { .. } | ExpType::Get { .. } | ExpType::OptionalGet { .. } | ExpType::Index { .. } => {
+                Ok(self.chain(expression)?.unwrap_or(Object::Nil))
+            }
+            ExpType::Interpolation { quote, parts } => {
+                let mut value = String::new();
+                for part in parts {
+                    let part = self.visit_expression(part)?.to_string();
+                    if value.len() + part.len() > MAX_STRING_LENGTH {
+                        return Err(LunalaErrors::new(ErrorTypes::StringTooLong(MAX_STRING_LENGTH), quote.line()));
+                    }
+                    value.push_str(&part);
+                }
+                Ok(Object::String(value))
+            }
+            ExpType::Function(declaration) => {
+                Ok(Object::Function(Rc::new(Function::new(declaration.clone(), self.environment.clone()))))
+            }
+            ExpType::List { elements } => {
+                let elements = elements.iter()
+                    .map(|element| self.visit_expression(element))
+                    .collect::<Result<Vec<Object>, LunalaErrors>>()?;
+                Ok(Object::new_list(elements))
+            }
+            ExpType::Tuple { elements } => {
+                let elements = elements.iter()
+                    .map(|element| self.visit_expression(element))
+                    .collect::<Result<Vec<Object>, LunalaErrors>>()?;
+                Ok(Object::Tuple(Rc::new(elements)))
+            }
+            ExpType::Map { brace, entries } => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    let key = self.visit_expression(key)?;
+                    let value = self.visit_expression(value)?;
+                    map.insert(key, value).map_err(|err| err.or_line(brace.line()))?;
+                }
+                Ok(Object::new_map(map))
+            }
+            ExpType::StructLiteral { name, fields } => {
+                let class = match self.environment.borrow().get(name)? {
+                    Object::Struct(class) => class,
+                    _ => return Err(LunalaErrors::new(ErrorTypes::NotAStruct(name.access_lexeme()), name.line())),
+                };
+                let mut given: Vec<(String, Object)> = Vec::new();
+                for (field, value) in fields {
+                    let value = self.visit_expression(value)?;
+                    let field_error = |error| LunalaErrors::new(error, field.line());
+                    if class.fields().all(|name| name != field.access_lexeme()) {
+                        return Err(field_error(ErrorTypes::UnknownField(class.name(), field.access_lexeme())));
+                    }
+                    if given.iter().any(|(name, _)| *name == field.access_lexeme()) {
+                        return Err(field_error(ErrorTypes::DuplicateField(class.name(), field.access_lexeme())));
+                    }
+                    given.push((field.access_lexeme(), value));
+                }
+                let mut values = Vec::new();
+                for field in class.fields() {
+                    match given.iter().position(|(name, _)| *name == field) {
+                        Some(index) => values.push(given.swap_remove(index).1),
+                        None => return Err(LunalaErrors::new(ErrorTypes::MissingField(class.name(), field), name.line())),
+                    }
+                }
+                Ok(Object::Instance(Rc::new(RefCell::new(Instance::new_struct(class, values)))))
+            }
+            ExpType::Match { keyword, subject, arms } => {
+                let subject = self.visit_expression(subject)?;
+                let (arm, scope) = self.select_arm(keyword, &subject, arms)?;
+                let previous = std::mem::replace(&mut self.environment, scope);
+                let value = self.visit_expression(arm.body());
+                self.environment = previous;
+                value
+            }
+            ExpType::SelfValue { keyword } => {
+                self.get_defined(keyword, SELF)
+            }
+            ExpType::Super { keyword, method } => {
+                let superclass = match self.get_defined(keyword, SUPER)? {
+                    Object::Class(superclass) => superclass,
+                    other => return Err(LunalaErrors::new(ErrorTypes::NotCallable(other.to_string()), keyword.line())),
+                };
+                let receiver = self.get_defined(keyword, SELF)?;
+                match superclass.find_method(&method.access_lexeme()) {
+                    Some(found) => Ok(Object::BoundMethod(Rc::new(BoundMethod::new(receiver, found)))),
+                    None => Err(LunalaErrors::new(
+                        ErrorTypes::UndefinedProperty(superclass.name().to_string(), method.access_lexeme()), method.line()
+                    )),
+                }
+            }
+            ExpType::Set { object: target, name, operator, value: expression } => {
+                let object = self.visit_expression(target)?;
+                if matches!(object, Object::Nil) {
+                    return Err(Self::nil_receiver(target, name));
+                }
+                let instance = Self::instance(&object).map_err(|err| err.or_line(name.line()))?;
+                let value = match operator.token_type().compound_operator() {
+                    Some(binary_operator) => {
+                        let current = Instance::get(&instance, &name.access_lexeme()).map_err(|err| err.or_line(name.line()))?;
+                        let value = self.visit_expression(expression)?;
+                        let target = ExpType::Get { object: target.clone(), name: name.clone() };
+                        Self::operate(binary_operator, operator.line(), (&target, &current), (expression, &value))?
+                    }
+                    None => self.visit_expression(expression)?,
+                };
+                instance.borrow_mut().set(&name.access_lexeme(), value.clone()).map_err(|err| err.or_line(name.line()))?;
+                Ok(value)
+            }
+            ExpType::IndexAssign { object: target, bracket, index: key, operator, value: expression } => {
+                let object = self.visit_expression(target)?;
+                let index = self.visit_expression(key)?;
+                let value = match operator.token_type().compound_operator() {
+                    Some(binary_operator) => {
+                        let current = Self::index(&object, &index).map_err(|err| err.or_line(bracket.line()))?;
+                        let value = self.visit_expression(expression)?;
+                        let target = ExpType::Index { object: target.clone(), bracket: bracket.clone(), index: key.clone() };
+                        Self::operate(binary_operator, operator.line(), (&target, &current), (expression, &value))?
+                    }
+                    None => self.visit_expression(expression)?,
+                };
+                Self::set_index(&object, index, value.clone()).map_err(|err| err.or_line(bracket.line()))?;
+                Ok(value)
             }
         }
     }
 
-    fn truthy(exp: ExpType) -> Result<bool, LunalaErrors> {
-        match exp {
-            ExpType::Literal(Literal::Bool(val)) => { Ok(val) },
-            _ => Err(LunalaErrors::new(ErrorTypes::NotABooleanValue(exp.to_string()), 0))
+    /// Evaluates a chain of calls, indexing and field access, `None` when a `?.` met `nil` and skipped the rest of it.
+    fn chain(&mut self, expression: &ExpType) -> Result<Option<Object>, LunalaErrors> {
+        match expression {
+            ExpType::Call { callee, paren, arguments } => {
+                let Some(callee) = self.chain(callee)? else { return Ok(None) };
+                let arguments = arguments.iter()
+                    .map(|argument| self.visit_expression(argument))
+                    .collect::<Result<Vec<Object>, LunalaErrors>>()?;
+                self.call(callee, arguments, paren).map(Some)
+            }
+            ExpType::Get { object: receiver, name } => {
+                let Some(object) = self.chain(receiver)? else { return Ok(None) };
+                if matches!(object, Object::Nil) {
+                    return Err(Self::nil_receiver(receiver, name));
+                }
+                Self::get(&object, &name.access_lexeme()).map(Some).map_err(|err| err.or_line(name.line()))
+            }
+            ExpType::OptionalGet { object, name } => {
+                match self.chain(object)? {
+                    None | Some(Object::Nil) => Ok(None),
+                    Some(object) => Self::get(&object, &name.access_lexeme()).map(Some).map_err(|err| err.or_line(name.line())),
+                }
+            }
+            ExpType::Index { object, bracket, index } => {
+                let Some(object) = self.chain(object)? else { return Ok(None) };
+                let index = self.visit_expression(index)?;
+                Self::index(&object, &index).map(Some).map_err(|err| err.or_line(bracket.line()))
+            }
+            _ => self.visit_expression(expression).map(Some),
+        }
+    }
+
+    fn call(&mut self, callee: Object, arguments: Vec<Object>, paren: &Token) -> Result<Object, LunalaErrors> {
+            match callee {
+                Object::Function(function) => self.call_function(&function, arguments, paren, None),
+                Object::BoundMethod(bound) => {
+                    self.call_function(bound.method(), arguments, paren, Some(bound.receiver().clone()))
+                }
+                Object::Class(class) => self.instantiate(class, arguments, paren),
+                Object::VariantConstructor(enumeration, index) => {
+                    let arity = enumeration.variant_fields(index).map_or(0, |fields| fields.len());
+                    if arguments.len() != arity {
+                        let name = format!("{}.{}", enumeration.name(), enumeration.variant_name(index));
+                        return Err(LunalaErrors::new(ErrorTypes::ArityMismatch(name, arity, arguments.len()), paren.line()));
+                    }
+                    Ok(Object::Variant(Rc::new(Variant::new(enumeration, index, arguments))))
+                }
+                Object::NativeFunction(function) => {
+                    if arguments.len() != function.arity() {
+                        return Err(LunalaErrors::new(
+                            ErrorTypes::ArityMismatch(function.name().to_string(), function.arity(), arguments.len()), paren.line()
+                        ));
+                    }
+                    function.call(&arguments).map_err(|err| err.or_line(paren.line()))
+                }
+                _ => Err(LunalaErrors::new(ErrorTypes::NotCallable(callee.to_string()), paren.line()))
+            }
+    }
+
+    /// A field or method of an instance, a variant of an enum or a field of an enum variant.
+    fn get(object: &Object, name: &str) -> Result<Object, LunalaErrors> {
+        match object {
+            Object::Enum(enumeration) => match enumeration.variant_index(name) {
+                Some(index) if enumeration.variant_fields(index).is_some() => {
+                    Ok(Object::VariantConstructor(enumeration.clone(), index))
+                }
+                Some(index) => Ok(Object::Variant(Rc::new(Variant::new(enumeration.clone(), index, Vec::new())))),
+                None => Err(LunalaErrors::new(ErrorTypes::UnknownVariant(enumeration.name(), name.to_string()), 0)),
+            },
+            Object::Variant(variant) => variant.field(name),
+            _ => Instance::get(&Self::instance(object)?, name),
+        }
+    }
+
+    fn instance(object: &Object) -> Result<Rc<RefCell<Instance>>, LunalaErrors> {
+        match object {
+            Object::Instance(instance) => Ok(instance.clone()),
+            _ => Err(LunalaErrors::new(ErrorTypes::NoFields(object.describe()), 0)),
+        }
+    }
+
+    fn index(object: &Object, index: &Object) -> Result<Object, LunalaErrors> {
+        match object {
+            Object::List(elements) => {
+                let elements = elements.borrow();
+                Ok(elements[Self::list_index(index, elements.len())?].clone())
+            }
+            Object::Tuple(elements) => Ok(elements[Self::list_index(index, elements.len())?].clone()),
+            Object::Map(map) => map.borrow().get(index),
+            _ => Err(LunalaErrors::new(ErrorTypes::NotIndexable(object.describe()), 0)),
+        }
+    }
+
+    /// Assigning to a missing map key inserts it, a list index has to exist already.
+    fn set_index(object: &Object, index: Object, value: Object) -> Result<(), LunalaErrors> {
+        match object {
+            Object::List(elements) => {
+                let position = Self::list_index(&index, elements.borrow().len())?;
+                elements.borrow_mut()[position] = value;
+                Ok(())
+            }
+            Object::Map(map) => map.borrow_mut().insert(index, value),
+            Object::Tuple(_) => Err(LunalaErrors::new(ErrorTypes::NotAssignable(object.describe()), 0)),
+            _ => Err(LunalaErrors::new(ErrorTypes::NotIndexable(object.describe()), 0)),
+        }
+    }
+
+    /// Resolves an index into a list or tuple of `length` elements, negative indices count from the end so `-1` is the last.
+    fn list_index(index: &Object, length: usize) -> Result<usize, LunalaErrors> {
+        let index = match index {
+            Object::Integer(index) => *index,
+            _ => return Err(LunalaErrors::new(ErrorTypes::InvalidIndex(index.describe()), 0)),
+        };
+        let position = if index < 0 { index.checked_add(length as IntegerPrecision) } else { Some(index) };
+        match position {
+            Some(position) if position >= 0 && (position as usize) < length => Ok(position as usize),
+            _ => Err(LunalaErrors::new(ErrorTypes::IndexOutOfBounds(index, length), 0)),
+        }
+    }
+
+    /// Methods are called with the instance they were accessed on as `receiver`, bound to `self` in their body.
+    fn call_function(&mut self, function: &Function, arguments: Vec<Object>, paren: &Token, receiver: Option<Object>) -> Result<Object, LunalaErrors> {
+        let declaration = function.declaration();
+        if arguments.len() != function.arity() {
+            return Err(LunalaErrors::new(
+                ErrorTypes::ArityMismatch(declaration.display_name(), function.arity(), arguments.len()), paren.line()
+            ));
+        }
+
+        let mut scope = Environment::new_enclosed(function.closure());
+        if let Some(receiver) = receiver {
+            scope.define(SELF, receiver);
+        }
+        for (param, argument) in declaration.params().iter().zip(arguments) {
+            scope.define(&param.access_lexeme(), argument);
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(LunalaErrors::new(ErrorTypes::CallDepthExceeded(MAX_CALL_DEPTH), paren.line()));
+        }
+        self.depth += 1;
+        let flow = self.execute_block(declaration.body(), scope);
+        self.depth -= 1;
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Object::Nil),
+        }
+    }
+
+    /// Creates an instance and runs its `init` method, if the class has one. The instance is the result,
+    /// whatever `init` returns.
+    fn instantiate(&mut self, class: Rc<Class>, arguments: Vec<Object>, paren: &Token) -> Result<Object, LunalaErrors> {
+        if arguments.len() != class.arity() {
+            return Err(LunalaErrors::new(
+                ErrorTypes::ArityMismatch(class.name().to_string(), class.arity(), arguments.len()), paren.line()
+            ));
+        }
+        let instance = Object::Instance(Rc::new(RefCell::new(Instance::new_class(class.clone()))));
+        if let Some(initializer) = class.find_method(INITIALIZER) {
+            self.call_function(&initializer, arguments, paren, Some(instance.clone()))?;
+        }
+        Ok(instance)
+    }
+
+    /// Looks up a variable the interpreter defines itself, like `self` in a method.
+    fn get_defined(&self, keyword: &Token, name: &str) -> Result<Object, LunalaErrors> {
+        self.environment.borrow().get(&Token::new(TokenType::Identifier, Some(name.to_string()), keyword.line()))
+    }
+
+    /// Applies a binary operator to evaluated operands, naming the operand's expression when it is `nil`.
+    fn operate(operator: TokenType, line: usize, left: (&ExpType, &Object), right: (&ExpType, &Object)) -> Result<Object, LunalaErrors> {
+        if !matches!(operator, TokenType::DoubleEquals | TokenType::BangEquals) {
+            for (expression, value) in [left, right] {
+                if matches!(value, Object::Nil) {
+                    return Err(Self::nil_operand(operator, line, expression));
+                }
+            }
+        }
+        Self::binary(operator, left.1, right.1).map_err(|err| err.or_line(line))
+    }
+
+    fn nil_operand(operator: TokenType, line: usize, expression: &ExpType) -> LunalaErrors {
+        LunalaErrors::new(ErrorTypes::NilOperand(expression.to_string(), operator.symbol()), line)
+    }
+
+    fn nil_receiver(receiver: &ExpType, name: &Token) -> LunalaErrors {
+        LunalaErrors::new(ErrorTypes::NilReceiver(receiver.to_string(), name.access_lexeme()), name.line())
+    }
+
+    fn binary(operator: TokenType, left: &Object, right: &Object) -> Result<Object, LunalaErrors> {
+        match (&operator, left, right) {
+            (TokenType::BangEquals, _, _) => Ok(Object::Bool(! Self::equal(left, right))),
+            (TokenType::DoubleEquals, _, _) => Ok(Object::Bool(Self::equal(left, right))),
+            (op, l, r) if op.is_bitwise() && !(l.is_integer() && r.is_integer()) => Err(Self::type_mismatch(operator, left, right)),
+            (_, l, r) if l.is_number() && r.is_number() => Self::numeric_binary(operator, l, r),
+            (_, Object::String(l), Object::String(r)) => Self::string_binary(operator, l, r),
+            (_, Object::Char(l), Object::Char(r)) => Self::char_binary(operator, *l, *r),
+            (TokenType::Plus, Object::String(l), Object::Char(r)) => Self::concat(l, &r.to_string()),
+            (TokenType::Plus, Object::Char(l), Object::String(r)) => Self::concat(&l.to_string(), r),
+            (TokenType::Star, Object::String(string), Object::Integer(count))
+            | (TokenType::Star, Object::Integer(count), Object::String(string)) => Self::repeat(string, *count),
+            _ => Err(Self::type_mismatch(operator, left, right)),
+        }
+    }
+
+    /// Picks the arithmetic for two numbers:
+    /// - two `i64` integers stay integers, promoting to a bigint instead of overflowing
+    /// - a float on either side makes it float arithmetic, unless the other side is a decimal
+    /// - a decimal with an integer of either width is exact decimal arithmetic
+    /// - an integer with a bigint is bigint arithmetic
+    fn numeric_binary(operator: TokenType, left: &Object, right: &Object) -> Result<Object, LunalaErrors> {
+        match (left, right) {
+            (Object::Integer(l), Object::Integer(r)) => Self::integer_binary(operator, *l, *r),
+            // Mixing exact and inexact numbers would silently lose the exactness
+            (Object::Decimal(_), Object::Float(_)) | (Object::Float(_), Object::Decimal(_)) => {
+                Err(Self::type_mismatch(operator, left, right))
+            }
+            (Object::Float(_), _) | (_, Object::Float(_)) => Self::float_binary(operator, left._get_number()?, right._get_number()?),
+            (Object::Decimal(_), _) | (_, Object::Decimal(_)) => match (left.to_decimal(), right.to_decimal()) {
+                (Some(l), Some(r)) => Self::decimal_binary(operator, &l, &r),
+                _ => Err(Self::type_mismatch(operator, left, right)),
+            },
+            _ => match (left.to_bigint(), right.to_bigint()) {
+                (Some(l), Some(r)) => Self::bigint_binary(operator, &l, &r),
+                _ => Err(Self::type_mismatch(operator, left, right)),
+            },
+        }
+    }
+
+    fn too_large(operator: TokenType, left: &dyn std::fmt::Display, right: &dyn std::fmt::Display) -> LunalaErrors {
+        LunalaErrors::new(ErrorTypes::ArithmeticError(
+            format!("`{} {} {}` would have more than {} bits", left, operator.symbol(), right, MAX_BITS)
+        ), 0)
+    }
+
+    fn divides_by_zero(operator: TokenType, left: &dyn std::fmt::Display, right: &dyn std::fmt::Display) -> LunalaErrors {
+        LunalaErrors::new(ErrorTypes::ArithmeticError(format!("`{} {} {}` divides by zero", left, operator.symbol(), right)), 0)
+    }
+
+    /// Division truncates towards zero and the remainder takes the sign of the dividend.
+    /// A result that doesn't fit an `i64` is computed again as a bigint, shifts always are.
+    fn integer_binary(operator: TokenType, left: IntegerPrecision, right: IntegerPrecision) -> Result<Object, LunalaErrors> {
+        let result = match operator {
+            TokenType::Plus => left.checked_add(right),
+            TokenType::Minus => left.checked_sub(right),
+            TokenType::Star => left.checked_mul(right),
+            TokenType::Slash | TokenType::Percent if right == 0 => {
+                return Err(Self::divides_by_zero(operator, &left, &right));
+            }
+            TokenType::Slash => left.checked_div(right),
+            TokenType::Percent => left.checked_rem(right),
+            // A negative exponent has a fractional result
+            TokenType::DoubleStar if right < 0 => return Self::float_binary(operator, left as Precision, right as Precision),
+            TokenType::DoubleStar => u32::try_from(right).ok().and_then(|exponent| left.checked_pow(exponent)),
+            TokenType::GreaterThan => return Ok(Object::Bool(left > right)),
+            TokenType::GreaterEquals => return Ok(Object::Bool(left >= right)),
+            TokenType::LessThan => return Ok(Object::Bool(left < right)),
+            TokenType::LessEquals => return Ok(Object::Bool(left <= right)),
+            TokenType::Ampersand => Some(left & right),
+            TokenType::Pipe => Some(left | right),
+            TokenType::Caret => Some(left ^ right),
+            TokenType::ShiftLeft | TokenType::ShiftRight => None,
+            _ => return Err(Self::type_mismatch(operator, &Object::Integer(left), &Object::Integer(right))),
+        };
+        match result {
+            Some(result) => Ok(Object::Integer(result)),
+            None => Self::bigint_binary(operator, &BigInt::from_i64(left), &BigInt::from_i64(right)),
+        }
+    }
+
+    fn bigint_binary(operator: TokenType, left: &BigInt, right: &BigInt) -> Result<Object, LunalaErrors> {
+        let result = match operator {
+            TokenType::Plus => left.add(right),
+            TokenType::Minus => left.sub(right),
+            TokenType::Star => left.mul(right),
+            TokenType::Slash | TokenType::Percent => match left.div_rem(right) {
+                Some((quotient, _)) if operator == TokenType::Slash => quotient,
+                Some((_, remainder)) => remainder,
+                None => return Err(Self::divides_by_zero(operator, left, right)),
+            },
+            TokenType::DoubleStar if right.is_negative() => return Self::float_binary(operator, left.to_f64(), right.to_f64()),
+            TokenType::DoubleStar => match right.to_i64().and_then(|exponent| u32::try_from(exponent).ok()) {
+                Some(exponent) => left.pow(exponent).ok_or_else(|| Self::too_large(operator, left, right))?,
+                None => return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(format!("exponent `{}` is too large", right)), 0)),
+            },
+            TokenType::GreaterThan => return Ok(Object::Bool(left > right)),
+            TokenType::GreaterEquals => return Ok(Object::Bool(left >= right)),
+            TokenType::LessThan => return Ok(Object::Bool(left < right)),
+            TokenType::LessEquals => return Ok(Object::Bool(left <= right)),
+            TokenType::Ampersand => left.bitwise(right, |l, r| l & r),
+            TokenType::Pipe => left.bitwise(right, |l, r| l | r),
+            TokenType::Caret => left.bitwise(right, |l, r| l ^ r),
+            TokenType::ShiftLeft => left.shl(Self::shift_amount(right)?).ok_or_else(|| Self::too_large(operator, left, right))?,
+            TokenType::ShiftRight => left.shr(Self::shift_amount(right)?),
+            _ => return Err(Self::type_mismatch(operator, &Object::BigInt(left.clone()), &Object::BigInt(right.clone()))),
+        };
+        Ok(Object::from_bigint(result))
+    }
+
+    /// Shifts need a non-negative count, `>>` is arithmetic so `-8 >> 1` is `-4`.
+    fn shift_amount(count: &BigInt) -> Result<u32, LunalaErrors> {
+        if count.is_negative() {
+            return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(format!("cannot shift by a negative count `{}`", count)), 0));
+        }
+        count.to_i64().and_then(|count| u32::try_from(count).ok()).ok_or_else(|| {
+            LunalaErrors::new(ErrorTypes::ArithmeticError(format!("shift count `{}` is too large", count)), 0)
+        })
+    }
+
+    /// Decimal arithmetic is exact, except for division which rounds non-terminating quotients.
+    /// Exponents have to be non-negative integers.
+    fn decimal_binary(operator: TokenType, left: &Decimal, right: &Decimal) -> Result<Object, LunalaErrors> {
+        let result = match operator {
+            TokenType::Plus => left.add(right),
+            TokenType::Minus => left.sub(right),
+            TokenType::Star => left.mul(right),
+            TokenType::Slash => left.div(right).ok_or_else(|| Self::divides_by_zero(operator.clone(), left, right))?,
+            TokenType::Percent => left.rem(right).ok_or_else(|| Self::divides_by_zero(operator.clone(), left, right))?,
+            TokenType::DoubleStar => match right.truncate().to_i64().and_then(|exponent| u32::try_from(exponent).ok()) {
+                Some(exponent) if Decimal::from_bigint(right.truncate()) == *right => {
+                    left.pow(exponent).ok_or_else(|| Self::too_large(operator, left, right))?
+                }
+                _ => return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(
+                    format!("a decimal can only be raised to a non-negative integer, got `{}`", right)
+                ), 0)),
+            },
+            TokenType::GreaterThan => return Ok(Object::Bool(left > right)),
+            TokenType::GreaterEquals => return Ok(Object::Bool(left >= right)),
+            TokenType::LessThan => return Ok(Object::Bool(left < right)),
+            TokenType::LessEquals => return Ok(Object::Bool(left <= right)),
+            _ => return Err(Self::type_mismatch(operator, &Object::Decimal(left.clone()), &Object::Decimal(right.clone()))),
+        };
+        Ok(Object::Decimal(result))
+    }
+
+    fn float_binary(operator: TokenType, left: Precision, right: Precision) -> Result<Object, LunalaErrors> {
+        match operator {
+            TokenType::Plus => Ok(Object::Float(left + right)),
+            TokenType::Minus => Ok(Object::Float(left - right)),
+            TokenType::Slash => Ok(Object::Float(left / right)),
+            TokenType::Percent => Ok(Object::Float(Self::modulo(left, right)?)),
+            TokenType::DoubleStar => Ok(Object::Float(Self::power(left, right)?)),
+            TokenType::Star => Ok(Object::Float(left * right)),
+            TokenType::GreaterThan => Ok(Object::Bool(left > right)),
+            TokenType::GreaterEquals => Ok(Object::Bool(left >= right)),
+            TokenType::LessThan => Ok(Object::Bool(left < right)),
+            TokenType::LessEquals => Ok(Object::Bool(left <= right)),
+            _ => Err(Self::type_mismatch(operator, &Object::Float(left), &Object::Float(right))),
+        }
+    }
+
+    fn string_binary(operator: TokenType, left: &str, right: &str) -> Result<Object, LunalaErrors> {
+        match operator {
+            TokenType::Plus => Self::concat(left, right),
+            // Lexicographic by unicode code point
+            TokenType::GreaterThan => Ok(Object::Bool(left > right)),
+            TokenType::GreaterEquals => Ok(Object::Bool(left >= right)),
+            TokenType::LessThan => Ok(Object::Bool(left < right)),
+            TokenType::LessEquals => Ok(Object::Bool(left <= right)),
+            _ => Err(Self::type_mismatch(operator, &Object::String(left.to_owned()), &Object::String(right.to_owned()))),
+        }
+    }
+
+    fn concat(left: &str, right: &str) -> Result<Object, LunalaErrors> {
+        match left.len() + right.len() <= MAX_STRING_LENGTH {
+            true => Ok(Object::String(format!("{}{}", left, right))),
+            false => Err(LunalaErrors::new(ErrorTypes::StringTooLong(MAX_STRING_LENGTH), 0)),
+        }
+    }
+
+    /// Chars order by their unicode code point.
+    fn char_binary(operator: TokenType, left: char, right: char) -> Result<Object, LunalaErrors> {
+        match operator {
+            TokenType::GreaterThan => Ok(Object::Bool(left > right)),
+            TokenType::GreaterEquals => Ok(Object::Bool(left >= right)),
+            TokenType::LessThan => Ok(Object::Bool(left < right)),
+            TokenType::LessEquals => Ok(Object::Bool(left <= right)),
+            _ => Err(Self::type_mismatch(operator, &Object::Char(left), &Object::Char(right))),
+        }
+    }
+
+    /// `"ab" * 3` is `"ababab"`, the count has to be a non-negative integer.
+    fn repeat(string: &str, count: IntegerPrecision) -> Result<Object, LunalaErrors> {
+        if count < 0 {
+            return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(format!("cannot repeat a string {} times", count)), 0));
+        }
+        let length = usize::try_from(count).ok().and_then(|count| string.len().checked_mul(count));
+        match length {
+            Some(length) if length <= MAX_STRING_LENGTH => Ok(Object::String(string.repeat(count as usize))),
+            _ => Err(LunalaErrors::new(ErrorTypes::ArithmeticError(format!("repeating a string {} times is too long", count)), 0)),
+        }
+    }
+
+    fn type_mismatch(operator: TokenType, left: &Object, right: &Object) -> LunalaErrors {
+        LunalaErrors::new(ErrorTypes::TypeMismatch(
+            operator.symbol(),
+            left.describe(),
+            right.describe(),
+        ), 0)
+    }
+
+    /// The remainder takes the sign of the dividend, so `-7 % 3` is `-1`.
+    /// A zero divisor or an infinite dividend has no meaningful remainder and is an error.
+    fn modulo(left: Precision, right: Precision) -> Result<Precision, LunalaErrors> {
+        if right == 0.0 {
+            return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(format!("`{} % {}` divides by zero", format_float(left), format_float(right))), 0));
+        }
+        if left.is_infinite() {
+            return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(format!("`{} % {}` has an infinite dividend", format_float(left), format_float(right))), 0));
+        }
+        Ok(left % right)
+    }
+
+    /// Follows `f64::powf`, so infinities propagate and `x ** 0` is `1`.
+    /// A negative base with a fractional exponent has no real result and is an error instead of NaN.
+    fn power(base: Precision, exponent: Precision) -> Result<Precision, LunalaErrors> {
+        if base < 0.0 && exponent.is_finite() && exponent.fract() != 0.0 {
+            // Parenthesised, as `**` binds tighter than the minus
+            let base = format!("({})", format_float(base));
+            return Err(LunalaErrors::new(ErrorTypes::ArithmeticError(
+                format!("`{} ** {}` has no real result", base, format_float(exponent))
+            ), 0));
+        }
+        Ok(base.powf(exponent))
+    }
+
+    fn truthy(object: &Object) -> Result<bool, LunalaErrors> {
+        match object {
+            Object::Bool(val) => { Ok(*val) },
+            _ => Err(LunalaErrors::new(ErrorTypes::NotABooleanValue(object.to_string()), 0))
         }
     }
 
     fn equal(left: &Object, right: &Object) -> bool {
+        Self::equal_within(left, right, &mut HashSet::new())
+    }
+
+    /// Compares two values, `comparing` holds the pairs of containers being compared around them.
+    /// Meeting a pair again means the containers hold themselves, and they are equal as far as that cycle goes.
+    fn equal_within(left: &Object, right: &Object, comparing: &mut HashSet<(*const (), *const ())>) -> bool {
+        let pair = left.container().zip(right.container());
+        if let Some(pair) = pair {
+            if !comparing.insert(pair) {
+                return true;
+            }
+        }
+        let equal = Self::compare(left, right, comparing);
+        if let Some(pair) = pair {
+            comparing.remove(&pair);
+        }
+        equal
+    }
+
+    fn compare(left: &Object, right: &Object, comparing: &mut HashSet<(*const (), *const ())>) -> bool {
         match (left, right) {
             (Object::Bool(l), Object::Bool(r)) => { l == r }
-            (Object::Number(l), Object::Number(r)) => { l == r}
+            (Object::Integer(l), Object::Integer(r)) => { l == r }
+            // Numbers compare by value across types, so `1 == 1.0` and `1.10d == 1.1d`
+            (l, r) if l.is_number() && r.is_number() => match (l.to_decimal(), r.to_decimal()) {
+                (Some(l), Some(r)) => l == r,
+                _ => l._get_number().ok() == r._get_number().ok(),
+            },
             (Object::String(l), Object::String(r)) => l == r,
+            (Object::Char(l), Object::Char(r)) => l == r,
+            (Object::List(l), Object::List(r)) => {
+                Rc::ptr_eq(l, r) || Self::elements_equal(&l.borrow(), &r.borrow(), comparing)
+            }
+            (Object::Tuple(l), Object::Tuple(r)) => Self::elements_equal(l, r, comparing),
+            // Maps are equal when they hold the same entries, in any order
+            (Object::Map(l), Object::Map(r)) => {
+                Rc::ptr_eq(l, r) || {
+                    let (l, r) = (l.borrow(), r.borrow());
+                    l.len() == r.len() && l.entries().iter().all(|(key, value)| match r.get(key) {
+                        Ok(other) => Self::equal_within(value, &other, comparing),
+                        Err(_) => false,
+                    })
+                }
+            }
+            (Object::NativeFunction(l), Object::NativeFunction(r)) => Rc::ptr_eq(l, r),
+            (Object::Struct(l), Object::Struct(r)) => Rc::ptr_eq(l, r),
+            (Object::Class(l), Object::Class(r)) => Rc::ptr_eq(l, r),
+            (Object::Enum(l), Object::Enum(r)) => Rc::ptr_eq(l, r),
+            (Object::Variant(l), Object::Variant(r)) => {
+                Rc::ptr_eq(l.enumeration(), r.enumeration()) && l.index() == r.index() && Self::elements_equal(l.values(), r.values(), comparing)
+            }
+            // Instances of the same struct or class are equal when their fields are
+            (Object::Instance(l), Object::Instance(r)) => {
+                Rc::ptr_eq(l, r) || {
+                    let (l, r) = (l.borrow(), r.borrow());
+                    l.is_instance_of(&r) && l.fields().len() == r.fields().len()
+                        && l.fields().iter().all(|(name, l)| r.field(name).is_some_and(|r| Self::equal_within(l, r, comparing)))
+                }
+            }
+            (Object::Function(l), Object::Function(r)) => Rc::ptr_eq(l, r),
+            (Object::Nil, Object::Nil) => true,
             (_, _) => {
                 false
             }
         }
     }
 
-    pub fn interpret(statements: Statements) -> Result<(), LunalaErrors> {
+    fn elements_equal(left: &[Object], right: &[Object], comparing: &mut HashSet<(*const (), *const ())>) -> bool {
+        left.len() == right.len() && left.iter().zip(right.iter()).all(|(l, r)| Self::equal_within(l, r, comparing))
+    }
+
+    pub fn interpret(&mut self, statements: Statements) -> Result<(), LunalaErrors> {
          //Self::visit_expression(&expression._get_type())
-        for statement in statements {
-            Self::execute(statement)?
+        for statement in &statements {
+            self.execute(statement)?;
         }
         Ok(())
     }
     
-    fn execute(statement: Statement) -> Result<(), LunalaErrors> {
+    fn execute(&mut self, statement: &Statement) -> Result<Flow, LunalaErrors> {
         match statement {
             Statement::Expression(exp) => {
-                Self::visit_expression_statement(exp)?
+                self.visit_expression_statement(exp)
             }
             Statement::Print(exp) => {
-                Self::visit_print_statement(exp)?
+                self.visit_print_statement(exp)
+            }
+            Statement::Let(declaration) => {
+                self.visit_let_statement(declaration)
+            }
+            Statement::Block(block) => {
+                let scope = Environment::new_enclosed(self.environment.clone());
+                self.execute_block(block.statements(), scope)
+            }
+            Statement::If(statement) => {
+                self.visit_if_statement(statement)
+            }
+            Statement::While(statement) => {
+                self.visit_while_statement(statement)
+            }
+            Statement::For(statement) => {
+                let scope = Environment::new_enclosed(self.environment.clone());
+                let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)));
+                let result = self.visit_for_statement(statement);
+                self.environment = previous;
+                result
+            }
+            Statement::Break(_) => Ok(Flow::Break),
+            Statement::Continue(_) => Ok(Flow::Continue),
+            Statement::Function(declaration) => {
+                let function = Object::Function(Rc::new(Function::new(declaration.clone(), self.environment.clone())));
+                if let Some(name) = declaration.name() {
+                    self.environment.borrow_mut().define(&name.access_lexeme(), function);
+                }
+                Ok(Flow::Normal)
+            }
+            Statement::Class(declaration) => {
+                self.visit_class_statement(declaration)
+            }
+            Statement::Enum(declaration) => {
+                let enumeration = Object::Enum(Rc::new(Enum::new(declaration.clone())));
+                self.environment.borrow_mut().define(&declaration.name().access_lexeme(), enumeration);
+                Ok(Flow::Normal)
+            }
+            Statement::Match(statement) => {
+                self.visit_match_statement(statement)
+            }
+            Statement::Struct(declaration) => {
+                let class = Object::Struct(Rc::new(Struct::new(declaration.clone())));
+                self.environment.borrow_mut().define(&declaration.name().access_lexeme(), class);
+                Ok(Flow::Normal)
+            }
+            Statement::Return(statement) => {
+                let value = match statement.value() {
+                    Some(value) => self.visit_expression(value)?,
+                    None => Object::Nil,
+                };
+                Ok(Flow::Return(value))
             }
         }
-        Ok(())
+    }
+
+    /// Runs `statements` inside `scope`, restoring the current scope afterwards even when one fails.
+    /// Stops early when a statement interrupts the normal flow, e.g. a `break`.
+    fn execute_block(&mut self, statements: &Statements, scope: Environment) -> Result<Flow, LunalaErrors> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)));
+        let mut result = Ok(Flow::Normal);
+        for statement in statements {
+            result = self.execute(statement);
+            if !matches!(result, Ok(Flow::Normal)) { break; }
+        }
+        self.environment = previous;
+        result
     }
     
-    fn visit_expression_statement(expression: statement::Expression) -> Result<(), LunalaErrors> {
-        Self::visit_expression(&expression.expression())?;
-        Ok(())
+    fn visit_expression_statement(&mut self, expression: &statement::Expression) -> Result<Flow, LunalaErrors> {
+        self.visit_expression(expression.expression())?;
+        Ok(Flow::Normal)
     }
     
-    fn visit_print_statement(expression: statement::PrintExpression) -> Result<(), LunalaErrors> {
-        let obj = Self::visit_expression(&expression.expression())?;
+    fn visit_print_statement(&mut self, expression: &statement::PrintExpression) -> Result<Flow, LunalaErrors> {
+        let obj = self.visit_expression(expression.expression())?;
         println!("{}", obj);
-        Ok(())
+        Ok(Flow::Normal)
+    }
+
+    /// The whole pattern is checked before anything is bound, a mismatch leaves the scope untouched.
+    fn visit_let_statement(&mut self, declaration: &statement::LetStatement) -> Result<Flow, LunalaErrors> {
+        let value = self.visit_expression(declaration.expression())?;
+        let mut bindings = Vec::new();
+        if !self.match_pattern(declaration.pattern(), &value, &mut bindings)? {
+            return Err(LunalaErrors::new(
+                ErrorTypes::PatternMismatch(declaration.pattern().to_string(), value.describe()), declaration.keyword().line()
+            ));
+        }
+        for (name, value) in bindings {
+            self.environment.borrow_mut().define(&name.access_lexeme(), value);
+        }
+        Ok(Flow::Normal)
     }
-}
\ No newline at end of file
+
+    /// Tests `value` against `pattern`, collecting what it binds. Bindings are only meaningful on a match.
+    /// Names in struct and variant patterns are looked up in the current scope.
+    fn match_pattern(&self, pattern: &Pattern, value: &Object, bindings: &mut Vec<(Token, Object)>) -> Result<bool, LunalaErrors> {
+        match (pattern, value) {
+            (Pattern::Binding(name), _) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            (Pattern::Wildcard, _) => Ok(true),
+            (Pattern::Literal(literal), _) => Ok(Self::equal(&Object::from_literal(literal.clone()), value)),
+            (Pattern::Range { start, end, inclusive }, _) => {
+                let (start, end) = (Object::from_literal(start.clone()), Object::from_literal(end.clone()));
+                let upper = if *inclusive { TokenType::LessEquals } else { TokenType::LessThan };
+                // Values that can't be compared to the bounds, like a string against numbers, are simply outside the range
+                let within = |operator: TokenType, left: &Object, right: &Object| {
+                    matches!(Self::binary(operator, left, right), Ok(Object::Bool(true)))
+                };
+                Ok(within(TokenType::LessEquals, &start, value) && within(upper, value, &end))
+            }
+            (Pattern::Tuple(patterns), Object::Tuple(elements)) if patterns.len() == elements.len() => {
+                self.match_elements(patterns, elements, bindings)
+            }
+            (Pattern::List { elements: patterns, rest }, Object::List(elements)) => {
+                let elements = elements.borrow().clone();
+                let fits = match rest {
+                    Some(_) => elements.len() >= patterns.len(),
+                    None => elements.len() == patterns.len(),
+                };
+                if !fits || !self.match_elements(patterns, &elements[..patterns.len()], bindings)? {
+                    return Ok(false);
+                }
+                if let Some(Some(rest)) = rest {
+                    bindings.push((rest.clone(), Object::new_list(elements[patterns.len()..].to_vec())));
+                }
+                Ok(true)
+            }
+            (Pattern::Map(fields), Object::Map(map)) => {
+                for (key, pattern) in fields {
+                    let field = map.borrow().get(&Object::String(key.access_lexeme()));
+                    match field {
+                        Ok(field) if self.match_pattern(pattern, &field, bindings)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                Ok(true)
+            }
+            (Pattern::Map(fields), Object::Instance(instance)) => self.match_fields(fields, instance, bindings),
+            (Pattern::Struct { name, fields }, Object::Instance(instance)) => {
+                let same_type = match (self.environment.borrow().get(name)?, instance.borrow().class()) {
+                    (Object::Struct(l), InstanceOf::Struct(r)) => Rc::ptr_eq(&l, r),
+                    (Object::Class(l), InstanceOf::Class(r)) => Rc::ptr_eq(&l, r),
+                    (Object::Struct(_) | Object::Class(_), _) => false,
+                    _ => return Err(LunalaErrors::new(ErrorTypes::NotAStruct(name.access_lexeme()), name.line())),
+                };
+                Ok(same_type && self.match_fields(fields, instance, bindings)?)
+            }
+            (Pattern::Variant { enum_name, variant: variant_name, fields }, _) => {
+                let (enumeration, index) = self.variant(enum_name, variant_name)?;
+                match value {
+                    Object::Variant(variant) if Rc::ptr_eq(variant.enumeration(), &enumeration) && variant.index() == index => {
+                        match fields {
+                            Some(patterns) if patterns.len() == variant.values().len() => {
+                                self.match_elements(patterns, variant.values(), bindings)
+                            }
+                            Some(_) => Ok(false),
+                            None => Ok(true),
+                        }
+                    }
+                    _ => Ok(false),
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn match_elements(&self, patterns: &[Pattern], elements: &[Object], bindings: &mut Vec<(Token, Object)>) -> Result<bool, LunalaErrors> {
+        for (pattern, element) in patterns.iter().zip(elements.iter()) {
+            if !self.match_pattern(pattern, element, bindings)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn match_fields(&self, fields: &[(Token, Pattern)], instance: &Rc<RefCell<Instance>>, bindings: &mut Vec<(Token, Object)>) -> Result<bool, LunalaErrors> {
+        for (key, pattern) in fields {
+            let field = instance.borrow().field(&key.access_lexeme()).cloned();
+            match field {
+                Some(field) if self.match_pattern(pattern, &field, bindings)? => {}
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Resolves `Enum.Variant` to the enum and the position of the variant.
+    fn variant(&self, enum_name: &Token, variant: &Token) -> Result<(Rc<Enum>, usize), LunalaErrors> {
+        let enumeration = match self.environment.borrow().get(enum_name)? {
+            Object::Enum(enumeration) => enumeration,
+            _ => return Err(LunalaErrors::new(ErrorTypes::NotAnEnum(enum_name.access_lexeme()), enum_name.line())),
+        };
+        match enumeration.variant_index(&variant.access_lexeme()) {
+            Some(index) => Ok((enumeration, index)),
+            None => Err(LunalaErrors::new(ErrorTypes::UnknownVariant(enumeration.name(), variant.access_lexeme()), variant.line())),
+        }
+    }
+
+    /// Finds the first arm matching `subject` whose guard holds, along with a scope holding its bindings.
+    /// Guards are evaluated in that scope, so they can use what the pattern bound.
+    fn select_arm<'a, T>(&mut self, keyword: &Token, subject: &Object, arms: &'a [MatchArm<T>]) -> Result<(&'a MatchArm<T>, Rc<RefCell<Environment>>), LunalaErrors> {
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if !self.match_pattern(arm.pattern(), subject, &mut bindings)? {
+                continue;
+            }
+            let mut scope = Environment::new_enclosed(self.environment.clone());
+            for (name, value) in bindings {
+                scope.define(&name.access_lexeme(), value);
+            }
+            let scope = Rc::new(RefCell::new(scope));
+            // The body runs in the scope the guard saw, so both share the bindings
+            let holds = match arm.guard() {
+                Some(guard) => {
+                    let previous = std::mem::replace(&mut self.environment, scope.clone());
+                    let value = self.visit_expression(guard);
+                    self.environment = previous;
+                    self.condition(keyword, &value?)?
+                }
+                None => true,
+            };
+            if holds {
+                return Ok((arm, scope));
+            }
+        }
+        Err(LunalaErrors::new(ErrorTypes::NoMatchingArm(subject.describe()), keyword.line()))
+    }
+
+    fn visit_match_statement(&mut self, statement: &statement::MatchStatement) -> Result<Flow, LunalaErrors> {
+        let subject = self.visit_expression(statement.expression())?;
+        let (arm, scope) = self.select_arm(statement.keyword(), &subject, statement.arms())?;
+        let previous = std::mem::replace(&mut self.environment, scope);
+        let flow = self.execute(arm.body());
+        self.environment = previous;
+        flow
+    }
+
+    /// Methods of a subclass close over a scope holding `super`, the class they were declared in inherits from.
+    fn visit_class_statement(&mut self, declaration: &statement::ClassStatement) -> Result<Flow, LunalaErrors> {
+        let name = declaration.name();
+        let superclass = match declaration.superclass() {
+            Some(superclass_name) => match self.environment.borrow().get(superclass_name)? {
+                Object::Class(superclass) => Some(superclass),
+                _ => return Err(LunalaErrors::new(
+                    ErrorTypes::InvalidSuperclass(name.access_lexeme(), superclass_name.access_lexeme()), superclass_name.line()
+                )),
+            },
+            None => None,
+        };
+        let closure = match &superclass {
+            Some(superclass) => {
+                let mut scope = Environment::new_enclosed(self.environment.clone());
+                scope.define(SUPER, Object::Class(superclass.clone()));
+                Rc::new(RefCell::new(scope))
+            }
+            None => self.environment.clone(),
+        };
+        let methods = declaration.methods().iter()
+            .map(|method| (method.display_name(), Rc::new(Function::new(method.clone(), closure.clone()))))
+            .collect();
+        let class = Class::new(name.access_lexeme(), superclass, methods);
+        self.environment.borrow_mut().define(&name.access_lexeme(), Object::Class(Rc::new(class)));
+        Ok(Flow::Normal)
+    }
+
+    fn visit_if_statement(&mut self, statement: &statement::IfStatement) -> Result<Flow, LunalaErrors> {
+        let condition = self.visit_expression(statement.expression())?;
+        if self.condition(statement.keyword(), &condition)? {
+            return self.execute(statement.then_branch());
+        } else if let Some(else_branch) = statement.else_branch() {
+            return self.execute(else_branch);
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn visit_while_statement(&mut self, statement: &statement::WhileStatement) -> Result<Flow, LunalaErrors> {
+        loop {
+            let condition = self.visit_expression(statement.expression())?;
+            if !self.condition(statement.keyword(), &condition)? { break; }
+            match self.execute(statement.body())? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Normal | Flow::Continue => {}
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Runs a `for` loop, the caller provides the scope holding the loop variable.
+    fn visit_for_statement(&mut self, statement: &statement::ForStatement) -> Result<Flow, LunalaErrors> {
+        if let Some(initializer) = statement.initializer() {
+            self.execute(initializer)?;
+        }
+        loop {
+            if let Some(condition) = statement.condition() {
+                let condition = self.visit_expression(condition)?;
+                if !self.condition(statement.keyword(), &condition)? { break; }
+            }
+            match self.execute(statement.body())? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Normal | Flow::Continue => {}
+            }
+            if let Some(increment) = statement.increment() {
+                self.visit_expression(increment)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Applies the `truthy` rules to the condition of a control flow statement.
+    fn condition(&self, keyword: &Token, condition: &Object) -> Result<bool, LunalaErrors> {
+        Self::truthy(condition).map_err(|_| LunalaErrors::new(
+            ErrorTypes::NonBooleanCondition(keyword.access_lexeme().to_lowercase(), condition.to_string()), keyword.line()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    /// Scans, parses and runs `source`, then reads the global `result` it left behind.
+    fn run(source: &str) -> Result<String, String> {
+        let tokens = Scanner::new(source).scan_tokens().map_err(|err| err.to_string())?;
+        let statements = Parser::new(tokens, Vec::new()).parse().map_err(|err| err.to_string())?;
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret(statements).map_err(|err| err.to_string())?;
+        let result = Token::new(TokenType::Identifier, Some("result".to_string()), 0);
+        let value = interpreter.environment.borrow().get(&result);
+        value.map(|value| value.to_string()).map_err(|err| err.to_string())
+    }
+
+    fn result(source: &str) -> String {
+        run(source).unwrap_or_else(|err| panic!("`{}` failed: {}", source, err))
+    }
+
+    fn error(source: &str) -> String {
+        run(source).expect_err(source)
+    }
+
+    #[test]
+    fn let_declares_and_looks_up_variables() {
+        assert_eq!(result("let a = 1; let b = a + 1; let result = b;"), "2");
+        assert_eq!(result("let a = 1; let a = a + 1; let result = a;"), "2");
+        assert_eq!(error("let result = missing;"), "Undefined variable `missing` at line 1");
+        assert!(error("let a;").contains("Expected a `=` after variable name."));
+    }
+
+    #[test]
+    fn assignment_and_compound_assignment() {
+        assert_eq!(result("let a = 1; let b = a = 5; let result = (a, b);"), "(5, 5)");
+        assert_eq!(result("let result = 7; result %= 4; result -= 1; result *= 3; result /= 2;"), "3");
+        assert_eq!(result("let result = \"a\"; result += \"b\"; result *= 2;"), "abab");
+        assert_eq!(error("1 = 2;"), "Invalid assignment target `1` at line 1");
+        assert_eq!(error("let a = 1; a + 1 = 2;"), "Invalid assignment target `a + 1` at line 1");
+    }
+
+    #[test]
+    fn compound_assignment_reads_the_target_before_the_value() {
+        assert_eq!(result("let result = 1; fn f() { result = 10; return 1; } result += f();"), "2");
+        assert_eq!(result("let result = [1]; fn f() { result[0] = 10; return 1; } result[0] += f();"), "[2]");
+    }
+
+    #[test]
+    fn blocks_scope_their_declarations() {
+        assert_eq!(result("let result = 1; { let result = 2; }"), "1");
+        assert_eq!(result("let result = 1; { let a = 2; { result = a + 1; } }"), "3");
+        assert_eq!(error("{ let b = 1; } let result = b;"), "Undefined variable `b` at line 1");
+    }
+
+    #[test]
+    fn if_else_chains() {
+        let sign = |x: i32| result(&format!("let result = 2; let x = {}; if (x > 0) result = 1; else if (x < 0) result = -1; else result = 0;", x));
+        assert_eq!((sign(5), sign(-5), sign(0)), ("1".to_string(), "-1".to_string(), "0".to_string()));
+        assert_eq!(result("let result = 0; if (true) if (false) result = 1; else result = 2;"), "2");
+        assert_eq!(error("if (1) print 1;"), "The condition of `if` must be a boolean, found `1` at line 1");
+    }
+
+    #[test]
+    fn loops_with_break_and_continue() {
+        let source = "let result = 0; for (let i = 0; i < 10; i += 1) { if (i == 2) continue; if (i == 5) break; result += i; }";
+        assert_eq!(result(source), "8");
+        assert_eq!(result("let result = 0; while (result < 3) result += 1;"), "3");
+        assert_eq!(result("let result = 0; while (true) { while (true) break; result += 1; if (result == 2) break; }"), "2");
+        assert_eq!(error("for (let i = 0; i < 1; i += 1) {} let result = i;"), "Undefined variable `i` at line 1");
+        assert_eq!(error("break;"), "`break` can only be used inside a loop at line 1");
+        assert_eq!(error("while (true) { fn f() { continue; } }"), "`continue` can only be used inside a loop at line 1");
+    }
+
+    #[test]
+    fn functions_calls_and_return() {
+        assert_eq!(result("fn fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } let result = fib(15);"), "610");
+        assert_eq!(result("fn f() {} let result = f();"), "nil");
+        assert_eq!(result("fn f() {} let result = f;"), "<fn f>");
+        assert_eq!(error("fn f(a, b) { return a + b; } f(1);"), "`f` expects 2 argument(s), got 1 at line 1");
+        assert_eq!(error("let x = 1; x();"), "`1` is not callable at line 1");
+        assert_eq!(error("return 1;"), "`return` can only be used inside a function at line 1");
+    }
+
+    /// Runs `run` on a stack as large as the interpreter thread's, the test thread's is far smaller.
+    fn on_interpreter_stack<T: Send + 'static>(run: impl FnOnce() -> T + Send + 'static) -> T {
+        let thread = std::thread::Builder::new().stack_size(crate::STACK_SIZE).spawn(run).unwrap();
+        thread.join().unwrap()
+    }
+
+    #[test]
+    fn runaway_recursion_is_an_error() {
+        assert_eq!(
+            on_interpreter_stack(|| error("fn f(n) { return f(n + 1); } f(0);")),
+            format!("Calls nested more than {} deep, is the recursion missing a base case? at line 1", MAX_CALL_DEPTH),
+        );
+    }
+
+    #[test]
+    fn deeply_nested_source_and_values_dont_overflow_the_stack() {
+        let source = format!("let result = {}1{};", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(on_interpreter_stack(move || error(&source)).starts_with("Source nested more than"));
+        let printed = on_interpreter_stack(|| result("let result = []; for (let i = 0; i < 100000; i += 1) result = [result];"));
+        assert!(printed.starts_with("[[[[") && printed.contains("[...]"));
+    }
+
+    #[test]
+    fn closures_capture_their_environment() {
+        let counter = "fn counter() { let n = 0; return fn() { n += 1; return n; }; }";
+        assert_eq!(result(&format!("{} let c = counter(); c(); let result = c();", counter)), "2");
+        assert_eq!(result(&format!("{} let c = counter(); c(); let d = counter(); let result = d();", counter)), "1");
+        assert_eq!(result("let result = (fn(x) { return x * 2; })(4);"), "8");
+        assert_eq!(result("let x = 1; fn f() { return x; } fn g() { let x = 2; return f(); } let result = g();"), "1");
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let calls = "let calls = 0; fn touch(value) { calls += 1; return value; }";
+        assert_eq!(result(&format!("{} let a = false and touch(true); let result = (a, calls);", calls)), "(false, 0)");
+        assert_eq!(result(&format!("{} let a = true or touch(false); let result = (a, calls);", calls)), "(true, 0)");
+        assert_eq!(result(&format!("{} let a = true and touch(false); let result = (a, calls);", calls)), "(false, 1)");
+        assert_eq!(error("let result = true and 1;"), "Expected a boolean, found 1 at line 1");
+    }
+
+    #[test]
+    fn modulo_and_power() {
+        assert_eq!(result("let result = (7 % 3, -7 % 3, 7.5 % 2);"), "(1, -1, 1.5)");
+        assert_eq!(result("let result = (2 ** 3 ** 2, 2 ** -1, -2 ** 2);"), "(512, 0.5, -4)");
+        assert_eq!(error("let result = 1 % 0;"), "Arithmetic error: `1 % 0` divides by zero at line 1");
+        assert_eq!(error("let result = 2.0 % 0.0;"), "Arithmetic error: `2.0 % 0.0` divides by zero at line 1");
+    }
+
+    #[test]
+    fn string_operators() {
+        assert_eq!(result("let result = (\"ab\" + \"c\", \"ab\" * 2, 3 * \"x\");"), "(\"abc\", \"abab\", \"xxx\")");
+        assert_eq!(result("let result = (\"a\" < \"b\", \"b\" >= \"ab\", \"ab\" == \"ab\");"), "(true, true, true)");
+        assert_eq!(error("let result = \"a\" + 1;"), "Unsupported operand types for `+`: string `a` and int `1` at line 1");
+        assert_eq!(error("let result = \"ab\" * -1;"), "Arithmetic error: cannot repeat a string -1 times at line 1");
+        assert_eq!(error("let result = \"ab\" * 200000000;"), "Arithmetic error: repeating a string 200000000 times is too long at line 1");
+    }
+
+    #[test]
+    fn template_literals() {
+        let source = r#"let name = "Luna"; let n = 2; let result = `Hi ${name}, ${n + 1} ${[1, "a"]} \${x}`;"#;
+        assert_eq!(result(source), r#"Hi Luna, 3 [1, "a"] ${x}"#);
+        assert_eq!(result("let result = `a${`b${1}`}c`;"), "ab1c");
+        assert_eq!(error("let result = `${missing}`;"), "Undefined variable `missing` at line 1");
+        assert_eq!(error("let result = `abc"), "Unterminated template literal at line 1");
+    }
+
+    #[test]
+    fn char_literals_and_conversions() {
+        assert_eq!(result("let result = ['a', 'a' < 'b', 'a' == 'a'];"), "['a', true, true]");
+        assert_eq!(result("let result = ('a' + \"bc\", \"x\" + 'y');"), "(\"abc\", \"xy\")");
+        assert_eq!(result("let result = (ord('A'), chr(66), char(\"z\"), str('q'));"), "(65, 'B', 'z', \"q\")");
+        assert_eq!(error("let result = 'a' + 'b';"), "Unsupported operand types for `+`: char `a` and char `b` at line 1");
+        assert_eq!(error("let result = char(\"ab\");"), "`char` expects a string of one character, got string `ab` at line 1");
+    }
+
+    #[test]
+    fn integers_and_floats_stay_distinct() {
+        assert_eq!(result("let result = (7 / 2, 7.0 / 2, 1 + 2.5, 3.0, 0.1 + 0.2);"), "(3, 3.5, 3.5, 3.0, 0.30000000000000004)");
+        assert_eq!(result("let result = (int(3.9), float(2), 1.0 / 0);"), "(3, 2.0, inf)");
+        assert_eq!(error("let result = 1 / 0;"), "Arithmetic error: `1 / 0` divides by zero at line 1");
+        assert_eq!(error("let result = -\"a\";"), "Unsupported operand type for `-`: string `a` at line 1");
+    }
+
+    #[test]
+    fn bigints_and_decimals() {
+        assert_eq!(result("let result = (9223372036854775807 + 1, (9223372036854775807 + 1) - 1);"), "(9223372036854775808, 9223372036854775807)");
+        assert_eq!(result("let result = (2 ** 100, 2 ** 64 / 2 ** 32);"), "(1267650600228229401496703205376, 4294967296)");
+        assert_eq!(result("let result = (1.10d + 2, 1d / 3d, decimal(\"0.1\") + decimal(\"0.2\"));"), "(3.10, 0.333333333333333333, 0.3)");
+        assert_eq!(result("let result = (i16(32767), i64(2 ** 63 - 1));"), "(32767, 9223372036854775807)");
+        assert_eq!(error("let result = i16(32768);"), "`i16` expects a value that converts to an integer within 16 bits, got int `32768` at line 1");
+        assert_eq!(error("let result = 1.5d + 1.5;"), "Unsupported operand types for `+`: decimal `1.5` and float `1.5` at line 1");
+        assert_eq!(error("let result = 2 ** 2000000;"), "Arithmetic error: `2 ** 2000000` would have more than 1048576 bits at line 1");
+    }
+
+    #[test]
+    fn number_literal_forms() {
+        assert_eq!(result("let result = (0xff + 0b1010 + 0o17, 1_000 * 2, 6.02e23, 1e-5 * 1e5);"), "(280, 2000, 6.02e23, 1.0)");
+        assert_eq!(result("let result = 0xffffffffffffffffff;"), "4722366482869645213695");
+    }
+
+    #[test]
+    fn bitwise_and_shift_operators() {
+        assert_eq!(result("let result = (12 & 10, 12 | 10, 12 ^ 10, ~5);"), "(8, 14, 6, -6)");
+        assert_eq!(result("let result = (1 << 70, -8 >> 1, -1 >> 100);"), "(1180591620717411303424, -4, -1)");
+        assert_eq!(error("let result = 1.5 & 1;"), "Unsupported operand types for `&`: float `1.5` and int `1` at line 1");
+        assert_eq!(error("let result = 1 << -1;"), "Arithmetic error: cannot shift by a negative count `-1` at line 1");
+    }
+
+    #[test]
+    fn list_literals_and_indexing() {
+        assert_eq!(result("let result = [1, 2, 3]; result[0] = 10; result[-1] += 1;"), "[10, 2, 4]");
+        assert_eq!(result("let l = [[1], 2]; let result = (l[0][0], l[-2], len(l), [1, 2] == [1, 2]);"), "(1, [1], 2, true)");
+        assert_eq!(error("let result = [1][1];"), "Index 1 is out of bounds for length 1 at line 1");
+        assert_eq!(error("let result = [1][\"a\"];"), "An index must be an integer, found string `a` at line 1");
+        assert_eq!(error("let result = 1[0];"), "int `1` cannot be indexed at line 1");
+    }
+
+    #[test]
+    fn self_containing_lists_print_and_compare() {
+        assert_eq!(result("let result = [1, 2]; result[1] = result;"), "[1, [...]]");
+        assert_eq!(result("let a = [1, 2]; a[1] = a; let b = [1, 2]; b[1] = b; let result = (a == b, [a, a]);"), "(true, [[1, [...]], [1, [...]]])");
+    }
+
+    #[test]
+    fn map_literals_keep_insertion_order() {
+        assert_eq!(result("let result = {\"a\": 1, 2: \"b\", true: 'c'}; result[\"d\"] = 3; result[\"a\"] += 1;"), "{\"a\": 2, 2: \"b\", true: 'c', \"d\": 3}");
+        assert_eq!(result("let m = {\"a\": 1, 2: \"b\"}; let result = (m[2], keys(m), values(m), len(m));"), "(\"b\", [\"a\", 2], [1, \"b\"], 2)");
+        assert_eq!(result("let result = {\"x\": 1, \"y\": 2} == {\"y\": 2, \"x\": 1};"), "true");
+        assert_eq!(error("let result = {\"a\": 1}[\"b\"];"), "Key \"b\" is not in the map at line 1");
+        assert_eq!(error("let result = {[1]: 2};"), "list `[1]` cannot be used as a map key, keys are strings, chars, integers or booleans at line 1");
+    }
+
+    #[test]
+    fn tuples_and_destructuring_let() {
+        assert_eq!(result("let t = (1, \"a\", (2, 3)); let result = (t, t[2][0], (1,), ());"), "((1, \"a\", (2, 3)), 2, (1,), ())");
+        assert_eq!(result("let (a, _, (b, c)) = (1, \"a\", (2, 3)); let [x, y] = [4, 5]; let result = a + b + c + x + y;"), "15");
+        assert_eq!(error("let (a, b) = (1, 2, 3);"), "Cannot destructure tuple `(1, 2, 3)` with the pattern `(a, b)` at line 1");
+        assert_eq!(error("let t = (1, 2); t[0] = 3;"), "The elements of tuple `(1, 2)` cannot be assigned at line 1");
+    }
+
+    #[test]
+    fn structs_with_field_access() {
+        let point = "struct Point { x, y }";
+        assert_eq!(result(&format!("{} let result = Point {{ x: 1, y: 2 }}; result.x += 10;", point)), "Point { x: 11, y: 2 }");
+        assert_eq!(result(&format!("{} let x = 5; let y = 6; let result = (Point {{ x, y }}, Point);", point)), "(Point { x: 5, y: 6 }, <struct Point>)");
+        assert_eq!(result(&format!("{} let result = Point {{ x: 1, y: 2 }} == Point {{ x: 1, y: 2 }};", point)), "true");
+        assert_eq!(error(&format!("{} let result = Point {{ x: 1 }};", point)), "Missing field `y` in `Point` at line 1");
+        assert_eq!(error(&format!("{} let p = Point {{ x: 1, y: 2 }}; p.z = 1;", point)), "`Point` has no field `z` at line 1");
+    }
+
+    #[test]
+    fn classes_with_methods_and_inheritance() {
+        let classes = "class Animal { fn init(name) { self.name = name; } fn speak() { return self.name + \" makes a sound\"; } }
+            class Dog < Animal { fn speak() { return super.speak() + \" (woof)\"; } }";
+        assert_eq!(result(&format!("{} let result = Dog(\"Rex\").speak();", classes)), "Rex makes a sound (woof)");
+        assert_eq!(result(&format!("{} let d = Dog(\"Rex\"); let speak = d.speak; let result = (d, speak());", classes)), "(Dog { name: \"Rex\" }, \"Rex makes a sound (woof)\")");
+        assert_eq!(error("class A { fn init(x) {} } A();"), "`A` expects 1 argument(s), got 0 at line 1");
+        assert_eq!(error("let result = self;"), "`self` can only be used inside a method at line 1");
+        assert_eq!(error("class A { fn f() { return super.f(); } }"), "`super` can only be used inside a method of a class with a superclass at line 1");
+        assert_eq!(error("let B = 1; class A < B {}"), "`A` cannot inherit from `B`, it is not a class at line 1");
+    }
+
+    /// The warnings from parsing each source in turn, every parser starting from the enums the earlier ones declared.
+    fn warnings(sources: &[&str]) -> Vec<String> {
+        let mut enums = Vec::new();
+        let mut warnings = Vec::new();
+        for source in sources {
+            let mut parser = Parser::new(Scanner::new(source).scan_tokens().unwrap(), enums);
+            parser.parse().unwrap();
+            warnings.extend_from_slice(parser.warnings());
+            enums = parser.enums().to_vec();
+        }
+        warnings
+    }
+
+    #[test]
+    fn enums_and_match() {
+        let shape = "enum Shape { Circle(r), Rect(w, h), Empty }
+            fn area(s) { return match s { Shape.Circle(r) => 3 * r * r, Shape.Rect(w, h) if w == h => w * w, Shape.Rect(w, h) => w * h, Shape.Empty => 0 }; }";
+        assert_eq!(result(&format!("{} let result = (area(Shape.Circle(2)), area(Shape.Rect(3, 3)), area(Shape.Rect(2, 5)), area(Shape.Empty));", shape)), "(12, 9, 10, 0)");
+        assert_eq!(result(&format!("{} let result = [Shape.Rect(1, 2), Shape.Empty];", shape)), "[Shape.Rect(1, 2), Shape.Empty]");
+        assert_eq!(result("let result = match (5) { 1..3 => \"low\", 3..=5 => \"mid\", _ => \"high\" };"), "mid");
+        assert_eq!(result("let result = match (1, \"a\") { (1, s) => s, _ => \"?\" };"), "a");
+        assert_eq!(error("let result = match 9 { 1 => \"one\" };"), "No `match` arm matches int `9` at line 1");
+        assert_eq!(error("enum E { A(x) } let result = E.A(1, 2);"), "`E.A` expects 1 argument(s), got 2 at line 1");
+    }
+
+    #[test]
+    fn match_arms_run_in_the_scope_their_guard_saw() {
+        let source = "let f = nil; match 3 { x if (fn() { f = fn() { return x; }; return true; })() => { x = 4; } } let result = f();";
+        assert_eq!(result(source), "4");
+    }
+
+    #[test]
+    fn match_exhaustiveness_warnings() {
+        assert_eq!(warnings(&["enum E { A, B } match E.A { E.A => print 1; }"]), ["`match` at line 1 doesn't cover E.B"]);
+        assert!(warnings(&["enum E { A, B } match E.A { E.A => print 1; _ => print 2; }"]).is_empty());
+        assert_eq!(warnings(&["enum E { A, B }", "match E.A { E.B => print 1; }"]), ["`match` at line 1 doesn't cover E.A"]);
+    }
+
+    #[test]
+    fn nil_and_null_safe_operators() {
+        let user = "struct User { address } struct Address { city } let user = User { address: Address { city: \"Oslo\" } }; let none = nil;";
+        assert_eq!(result(&format!("{} let result = (none?.address.city, user?.address.city);", user)), "(nil, \"Oslo\")");
+        assert_eq!(result("let result = (nil ?? \"default\", 0 ?? 1, nil == nil);"), "(\"default\", 0, true)");
+        assert_eq!(result("fn f() { return nil; } let result = f()?.x ?? \"none\";"), "none");
+        assert_eq!(error("let n = nil; let result = n + 1;"), "`n` is nil and can't be used with `+` at line 1");
+        assert_eq!(error("let n = nil; let result = n.city;"), "`n` is nil, so it has no field `city` at line 1");
+    }
+}
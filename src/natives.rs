@@ -0,0 +1,173 @@
+use crate::bignum::{BigInt, Decimal};
+use crate::environment::Environment;
+use crate::errors::{ErrorTypes, LunalaErrors};
+use crate::expressions::{IntegerPrecision, IntegerWidth, Precision};
+use crate::object::{NativeFunction, Object};
+use std::rc::Rc;
+
+/// Defines the built-in functions available to every script.
+pub fn define_natives(environment: &mut Environment) {
+    let natives = [
+        NativeFunction::new("str", 1, str),
+        NativeFunction::new("char", 1, char),
+        NativeFunction::new("ord", 1, ord),
+        NativeFunction::new("chr", 1, chr),
+        NativeFunction::new("int", 1, int),
+        NativeFunction::new("i16", 1, i16),
+        NativeFunction::new("i32", 1, i32),
+        NativeFunction::new("i64", 1, i64),
+        NativeFunction::new("i128", 1, i128),
+        NativeFunction::new("float", 1, float),
+        NativeFunction::new("decimal", 1, decimal),
+        NativeFunction::new("len", 1, len),
+        NativeFunction::new("keys", 1, keys),
+        NativeFunction::new("values", 1, values),
+    ];
+    for native in natives {
+        environment.define(native.name(), Object::NativeFunction(Rc::new(native)));
+    }
+}
+
+fn invalid_argument(function: &str, expected: &str, found: &Object) -> LunalaErrors {
+    LunalaErrors::new(ErrorTypes::InvalidArgument(
+        function.to_string(), expected.to_string(), found.describe(),
+    ), 0)
+}
+
+/// `str(value)`, the string form of any value.
+fn str(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    Ok(Object::String(arguments[0].to_string()))
+}
+
+/// `char(value)`, a string of exactly one character as a char.
+fn char(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    match &arguments[0] {
+        Object::Char(c) => Ok(Object::Char(*c)),
+        Object::String(string) => {
+            let mut chars = string.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Object::Char(c)),
+                _ => Err(invalid_argument("char", "a string of one character", &arguments[0])),
+            }
+        }
+        other => Err(invalid_argument("char", "a string of one character", other)),
+    }
+}
+
+/// `ord(c)`, the unicode code point of a char.
+fn ord(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    match &arguments[0] {
+        Object::Char(c) => Ok(Object::Integer(*c as IntegerPrecision)),
+        other => Err(invalid_argument("ord", "a char", other)),
+    }
+}
+
+/// `chr(code_point)`, the char of a unicode code point.
+fn chr(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    let code_point = match &arguments[0] {
+        Object::Integer(integer) => u32::try_from(*integer).ok().and_then(char::from_u32),
+        _ => None,
+    };
+    match code_point {
+        Some(c) => Ok(Object::Char(c)),
+        None => Err(invalid_argument("chr", "a unicode code point", &arguments[0])),
+    }
+}
+
+/// `int(value)`, converts a float or decimal by truncating towards zero or parses a string.
+fn int(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    let integer = match &arguments[0] {
+        Object::Integer(integer) => Some(Object::Integer(*integer)),
+        Object::BigInt(integer) => Some(Object::BigInt(integer.clone())),
+        // `as` saturates, so the range is checked first
+        Object::Float(float) if float.is_finite() && float.trunc() >= IntegerPrecision::MIN as Precision
+            && float.trunc() < IntegerPrecision::MAX as Precision => Some(Object::Integer(*float as IntegerPrecision)),
+        Object::Decimal(decimal) => Some(Object::from_bigint(decimal.truncate())),
+        Object::String(string) => BigInt::parse(string.trim()).map(Object::from_bigint),
+        _ => None,
+    };
+    match integer {
+        Some(integer) => Ok(integer),
+        None => Err(invalid_argument("int", "a finite number in range or a string holding an integer", &arguments[0])),
+    }
+}
+
+/// `i16(value)` to `i128(value)` convert like `int`, then check the integer fits that width.
+fn fixed_width(width: IntegerWidth, arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    let expected = format!("a value that converts to an integer within {} bits", width.bits());
+    let integer = int(arguments).map_err(|_| invalid_argument(width.name(), &expected, &arguments[0]))?;
+    match integer.to_bigint() {
+        Some(value) if width.contains(&value) => Ok(integer),
+        _ => Err(invalid_argument(width.name(), &expected, &arguments[0])),
+    }
+}
+
+fn i16(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    fixed_width(IntegerWidth::I16, arguments)
+}
+
+fn i32(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    fixed_width(IntegerWidth::I32, arguments)
+}
+
+fn i64(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    fixed_width(IntegerWidth::I64, arguments)
+}
+
+fn i128(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    fixed_width(IntegerWidth::I128, arguments)
+}
+
+/// `float(value)`, converts an integer or decimal to the nearest float or parses a string.
+fn float(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    let float = match &arguments[0] {
+        Object::String(string) => string.trim().parse::<Precision>().ok(),
+        number if number.is_number() => number._get_number().ok(),
+        _ => None,
+    };
+    match float {
+        Some(float) => Ok(Object::Float(float)),
+        None => Err(invalid_argument("float", "a number or a string holding a number", &arguments[0])),
+    }
+}
+
+/// `decimal(value)`, converts an integer exactly, a float by its shortest printed form, or parses a string.
+fn decimal(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    let decimal = match &arguments[0] {
+        Object::Float(float) if float.is_finite() => Decimal::parse(&float.to_string()),
+        Object::String(string) => Decimal::parse(string.trim()),
+        other => other.to_decimal(),
+    };
+    match decimal {
+        Some(decimal) => Ok(Object::Decimal(decimal)),
+        None => Err(invalid_argument("decimal", "a finite number or a string holding a decimal", &arguments[0])),
+    }
+}
+
+/// `len(value)`, the number of elements of a list or tuple, entries of a map or characters of a string.
+fn len(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    let length = match &arguments[0] {
+        Object::List(elements) => elements.borrow().len(),
+        Object::Map(map) => map.borrow().len(),
+        Object::Tuple(elements) => elements.len(),
+        Object::String(string) => string.chars().count(),
+        other => return Err(invalid_argument("len", "a list, tuple, map or string", other)),
+    };
+    Ok(Object::Integer(length as IntegerPrecision))
+}
+
+/// `keys(map)`, the keys of a map as a list, in insertion order.
+fn keys(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    match &arguments[0] {
+        Object::Map(map) => Ok(Object::new_list(map.borrow().entries().iter().map(|(key, _)| key.clone()).collect())),
+        other => Err(invalid_argument("keys", "a map", other)),
+    }
+}
+
+/// `values(map)`, the values of a map as a list, in the order of their keys.
+fn values(arguments: &[Object]) -> Result<Object, LunalaErrors> {
+    match &arguments[0] {
+        Object::Map(map) => Ok(Object::new_list(map.borrow().entries().iter().map(|(_, value)| value.clone()).collect())),
+        other => Err(invalid_argument("values", "a map", other)),
+    }
+}
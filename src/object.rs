@@ -0,0 +1,615 @@
+use crate::bignum::{BigInt, Decimal};
+use crate::environment::Environment;
+use crate::errors::{ErrorTypes, LunalaErrors};
+use crate::expressions::{format_float, IntegerPrecision, Literal, Precision};
+use crate::statement::{EnumStatement, FunctionStatement, StructStatement};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// A runtime value produced by the interpreter.
+#[derive(Clone)]
+pub enum Object {
+    Integer(IntegerPrecision),
+    /// An integer outside the `i64` range, results that fit again become an `Integer`.
+    BigInt(BigInt),
+    Float(Precision),
+    Decimal(Decimal),
+    Bool(bool),
+    String(String),
+    Char(char),
+    /// A list is shared, copies of it see each other's changes.
+    List(Rc<RefCell<Vec<Object>>>),
+    /// Shared like a list.
+    Map(Rc<RefCell<Map>>),
+    /// A fixed group of values, unlike a list it can't be changed after it's made.
+    Tuple(Rc<Vec<Object>>),
+    Function(Rc<Function>),
+    NativeFunction(Rc<NativeFunction>),
+    Struct(Rc<Struct>),
+    Class(Rc<Class>),
+    /// A value built from a struct or class, shared like a list.
+    Instance(Rc<RefCell<Instance>>),
+    BoundMethod(Rc<BoundMethod>),
+    Enum(Rc<Enum>),
+    /// A value of an enum, like `Shape.Circle(2)`.
+    Variant(Rc<Variant>),
+    /// Accessing a variant that has fields gives its constructor, calling it makes the value.
+    VariantConstructor(Rc<Enum>, usize),
+    /// The absent value, written `nil`. Functions that return without a value also give `nil`.
+    Nil,
+}
+
+/// An insertion ordered map, iterating it yields the entries in the order their keys were first inserted.
+pub struct Map {
+    indices: HashMap<MapKey, usize>,
+    entries: Vec<(Object, Object)>,
+}
+
+/// The hashable form of the values allowed as map keys.
+#[derive(PartialEq, Eq, Hash)]
+enum MapKey {
+    Integer(IntegerPrecision),
+    BigInt(BigInt),
+    Bool(bool),
+    String(String),
+    Char(char),
+}
+
+impl MapKey {
+    fn new(key: &Object) -> Result<MapKey, LunalaErrors> {
+        match key {
+            Object::Integer(value) => Ok(MapKey::Integer(*value)),
+            Object::BigInt(value) => Ok(MapKey::BigInt(value.clone())),
+            Object::Bool(value) => Ok(MapKey::Bool(*value)),
+            Object::String(value) => Ok(MapKey::String(value.clone())),
+            Object::Char(value) => Ok(MapKey::Char(*value)),
+            _ => Err(LunalaErrors::new(ErrorTypes::InvalidMapKey(key.describe()), 0)),
+        }
+    }
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map { indices: HashMap::new(), entries: Vec::new() }
+    }
+
+    pub fn get(&self, key: &Object) -> Result<Object, LunalaErrors> {
+        match self.indices.get(&MapKey::new(key)?) {
+            Some(index) => Ok(self.entries[*index].1.clone()),
+            None => Err(LunalaErrors::new(ErrorTypes::MissingKey(key.element_string(&mut HashSet::new(), 0)), 0)),
+        }
+    }
+
+    /// Replaces the value of an existing key in place, so it keeps its position.
+    pub fn insert(&mut self, key: Object, value: Object) -> Result<(), LunalaErrors> {
+        let hashed = MapKey::new(&key)?;
+        match self.indices.get(&hashed) {
+            Some(index) => self.entries[*index].1 = value,
+            None => {
+                self.indices.insert(hashed, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> &[(Object, Object)] {
+        &self.entries
+    }
+}
+
+/// A user-defined function, declared with `fn`.
+/// It keeps the scope it was defined in alive, so its body sees the variables around it by reference.
+pub struct Function {
+    declaration: Rc<FunctionStatement>,
+    closure: Rc<RefCell<Environment>>,
+}
+
+impl Function {
+    pub fn new(declaration: Rc<FunctionStatement>, closure: Rc<RefCell<Environment>>) -> Function {
+        Function { declaration, closure }
+    }
+
+    pub fn declaration(&self) -> &FunctionStatement {
+        &self.declaration
+    }
+
+    pub fn closure(&self) -> Rc<RefCell<Environment>> {
+        self.closure.clone()
+    }
+
+    pub fn arity(&self) -> usize {
+        self.declaration.params().len()
+    }
+}
+
+/// A struct type, declared with `struct`.
+pub struct Struct {
+    declaration: Rc<StructStatement>,
+}
+
+impl Struct {
+    pub fn new(declaration: Rc<StructStatement>) -> Struct {
+        Struct { declaration }
+    }
+
+    pub fn name(&self) -> String {
+        self.declaration.name().access_lexeme()
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = String> + '_ {
+        self.declaration.fields().iter().map(|field| field.access_lexeme())
+    }
+}
+
+/// A class, declared with `class`. Its methods are looked up through the superclass chain.
+pub struct Class {
+    name: String,
+    superclass: Option<Rc<Class>>,
+    methods: HashMap<String, Rc<Function>>,
+}
+
+impl Class {
+    pub fn new(name: String, superclass: Option<Rc<Class>>, methods: HashMap<String, Rc<Function>>) -> Class {
+        Class { name, superclass, methods }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+        match self.methods.get(name) {
+            Some(method) => Some(method.clone()),
+            None => self.superclass.as_ref().and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    /// Calling the class runs `init` with the call's arguments, a class without one takes none.
+    pub fn arity(&self) -> usize {
+        self.find_method(INITIALIZER).map_or(0, |initializer| initializer.arity())
+    }
+}
+
+/// The name of the method that initializes new instances of a class.
+pub const INITIALIZER: &str = "init";
+
+/// How deep values nest inside each other before printing leaves the rest out, so printing can't overflow the stack.
+const MAX_PRINT_DEPTH: usize = 1000;
+
+/// What an instance was made from.
+pub enum InstanceOf {
+    Struct(Rc<Struct>),
+    Class(Rc<Class>),
+}
+
+impl InstanceOf {
+    pub fn name(&self) -> String {
+        match self {
+            InstanceOf::Struct(class) => class.name(),
+            InstanceOf::Class(class) => class.name().to_string(),
+        }
+    }
+
+    fn same(&self, other: &InstanceOf) -> bool {
+        match (self, other) {
+            (InstanceOf::Struct(l), InstanceOf::Struct(r)) => Rc::ptr_eq(l, r),
+            (InstanceOf::Class(l), InstanceOf::Class(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
+}
+
+/// The fields of a struct or class instance, in the order they were declared or first assigned.
+pub struct Instance {
+    class: InstanceOf,
+    fields: Vec<(String, Object)>,
+}
+
+impl Instance {
+    /// Expects a value for every field of the struct, in declaration order.
+    pub fn new_struct(class: Rc<Struct>, values: Vec<Object>) -> Instance {
+        let fields = class.fields().zip(values).collect();
+        Instance { class: InstanceOf::Struct(class), fields }
+    }
+
+    /// A class instance starts without fields, its methods add them by assigning to `self`.
+    pub fn new_class(class: Rc<Class>) -> Instance {
+        Instance { class: InstanceOf::Class(class), fields: Vec::new() }
+    }
+
+    pub fn class(&self) -> &InstanceOf {
+        &self.class
+    }
+
+    pub fn is_instance_of(&self, other: &Instance) -> bool {
+        self.class.same(&other.class)
+    }
+
+    /// A field, or for class instances a method bound to `instance`. Fields shadow methods.
+    pub fn get(instance: &Rc<RefCell<Instance>>, name: &str) -> Result<Object, LunalaErrors> {
+        let this = instance.borrow();
+        if let Some((_, value)) = this.fields.iter().find(|(field, _)| field == name) {
+            return Ok(value.clone());
+        }
+        match &this.class {
+            InstanceOf::Class(class) => match class.find_method(name) {
+                Some(method) => Ok(Object::BoundMethod(Rc::new(BoundMethod::new(Object::Instance(instance.clone()), method)))),
+                None => Err(LunalaErrors::new(ErrorTypes::UndefinedProperty(class.name().to_string(), name.to_string()), 0)),
+            },
+            InstanceOf::Struct(class) => Err(LunalaErrors::new(ErrorTypes::UnknownField(class.name(), name.to_string()), 0)),
+        }
+    }
+
+    /// Only declared fields of a struct can be set, so a typo doesn't silently add a new one.
+    /// Class instances take any field.
+    pub fn set(&mut self, name: &str, value: Object) -> Result<(), LunalaErrors> {
+        match self.fields.iter_mut().find(|(field, _)| field == name) {
+            Some((_, slot)) => *slot = value,
+            None => match &self.class {
+                InstanceOf::Class(_) => self.fields.push((name.to_string(), value)),
+                InstanceOf::Struct(class) => {
+                    return Err(LunalaErrors::new(ErrorTypes::UnknownField(class.name(), name.to_string()), 0));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Object> {
+        self.fields.iter().find(|(field, _)| field == name).map(|(_, value)| value)
+    }
+
+    pub fn fields(&self) -> &[(String, Object)] {
+        &self.fields
+    }
+}
+
+/// A method together with the instance it was accessed on, `self` is bound to `receiver` when it's called.
+pub struct BoundMethod {
+    receiver: Object,
+    method: Rc<Function>,
+}
+
+impl BoundMethod {
+    pub fn new(receiver: Object, method: Rc<Function>) -> BoundMethod {
+        BoundMethod { receiver, method }
+    }
+
+    pub fn receiver(&self) -> &Object {
+        &self.receiver
+    }
+
+    pub fn method(&self) -> &Function {
+        &self.method
+    }
+}
+
+/// An enum type, declared with `enum`. Variants are referred to by their position in the declaration.
+pub struct Enum {
+    declaration: Rc<EnumStatement>,
+}
+
+impl Enum {
+    pub fn new(declaration: Rc<EnumStatement>) -> Enum {
+        Enum { declaration }
+    }
+
+    pub fn name(&self) -> String {
+        self.declaration.name().access_lexeme()
+    }
+
+    pub fn variant_index(&self, name: &str) -> Option<usize> {
+        self.declaration.variants().iter().position(|variant| variant.name().access_lexeme() == name)
+    }
+
+    pub fn variant_name(&self, index: usize) -> String {
+        self.declaration.variants()[index].name().access_lexeme()
+    }
+
+    /// The field names of a variant, `None` for a unit variant.
+    pub fn variant_fields(&self, index: usize) -> Option<Vec<String>> {
+        self.declaration.variants()[index].fields().map(|fields| fields.iter().map(|field| field.access_lexeme()).collect())
+    }
+}
+
+/// A value of one variant of an enum, with a value for each field of the variant.
+pub struct Variant {
+    enumeration: Rc<Enum>,
+    index: usize,
+    values: Vec<Object>,
+}
+
+impl Variant {
+    pub fn new(enumeration: Rc<Enum>, index: usize, values: Vec<Object>) -> Variant {
+        Variant { enumeration, index, values }
+    }
+
+    pub fn enumeration(&self) -> &Rc<Enum> {
+        &self.enumeration
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn values(&self) -> &[Object] {
+        &self.values
+    }
+
+    /// The qualified name of the variant, like `Shape.Circle`.
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.enumeration.name(), self.enumeration.variant_name(self.index))
+    }
+
+    pub fn field(&self, name: &str) -> Result<Object, LunalaErrors> {
+        let fields = self.enumeration.variant_fields(self.index).unwrap_or_default();
+        match fields.iter().position(|field| field == name) {
+            Some(index) => Ok(self.values[index].clone()),
+            None => Err(LunalaErrors::new(ErrorTypes::UnknownField(self.name(), name.to_string()), 0)),
+        }
+    }
+}
+
+/// A function provided by the interpreter itself, see `natives.rs`.
+pub struct NativeFunction {
+    name: &'static str,
+    arity: usize,
+    function: fn(&[Object]) -> Result<Object, LunalaErrors>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &'static str, arity: usize, function: fn(&[Object]) -> Result<Object, LunalaErrors>) -> NativeFunction {
+        NativeFunction { name, arity, function }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(&self, arguments: &[Object]) -> Result<Object, LunalaErrors> {
+        (self.function)(arguments)
+    }
+}
+
+impl Object {
+    pub fn from_literal(literal: Literal) -> Object {
+        match literal {
+            Literal::Integer(integer) => {
+                Object::Integer(integer)
+            }
+            Literal::BigInt(integer) => {
+                Object::from_bigint(integer)
+            }
+            Literal::Float(float) => {
+                Object::Float(float)
+            }
+            Literal::Decimal(decimal) => {
+                Object::Decimal(decimal)
+            }
+            Literal::Bool(bool) => {
+                Object::Bool(bool)
+            }
+            Literal::String(string) => {
+                Object::String(string)
+            }
+            Literal::Char(char) => {
+                Object::Char(char)
+            }
+            Literal::Nil => {
+                Object::Nil
+            }
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer(_) | Object::BigInt(_) => "int",
+            Object::Float(_) => "float",
+            Object::Decimal(_) => "decimal",
+            Object::Bool(_) => "bool",
+            Object::String(_) => "string",
+            Object::Char(_) => "char",
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+            Object::Tuple(_) => "tuple",
+            Object::Function(_) | Object::NativeFunction(_) | Object::BoundMethod(_) => "function",
+            Object::Struct(_) => "struct",
+            Object::Class(_) => "class",
+            Object::Enum(_) => "enum",
+            Object::Variant(_) => "variant",
+            Object::VariantConstructor(..) => "function",
+            Object::Instance(_) => "instance",
+            Object::Nil => "nil",
+        }
+    }
+
+    fn get_string(&self) -> String {
+        self.string_within(&mut HashSet::new(), 0)
+    }
+
+    /// Formats the value, `visiting` holds the lists, maps and instances being printed around it.
+    /// A container that holds itself, or a value nested `MAX_PRINT_DEPTH` deep, prints its elements as `...`.
+    fn string_within(&self, visiting: &mut HashSet<*const ()>, depth: usize) -> String {
+        let container = self.container();
+        let revisited = container.is_some_and(|container| visiting.contains(&container));
+        if revisited || depth >= MAX_PRINT_DEPTH {
+            if let Some(elided) = self.elided() {
+                return elided;
+            }
+        }
+        if let Some(container) = container {
+            visiting.insert(container);
+        }
+        let string = self.format_within(visiting, depth);
+        if let Some(container) = container {
+            visiting.remove(&container);
+        }
+        string
+    }
+
+    fn format_within(&self, visiting: &mut HashSet<*const ()>, depth: usize) -> String {
+        match self {
+            Object::Integer(value) => value.to_string(),
+            Object::BigInt(value) => value.to_string(),
+            Object::Float(value) => format_float(*value),
+            Object::Decimal(value) => value.to_string(),
+            Object::Bool(value) => value.to_string(),
+            Object::String(value) => value.to_string(),
+            Object::Char(value) => value.to_string(),
+            Object::List(elements) => {
+                let elements: Vec<String> = elements.borrow().iter().map(|element| element.element_string(visiting, depth + 1)).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Object::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.element_string(visiting, depth + 1)).collect();
+                match elements.len() {
+                    1 => format!("({},)", elements[0]),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            Object::Map(map) => {
+                let entries: Vec<String> = map.borrow().entries().iter()
+                    .map(|(key, value)| format!("{}: {}", key.element_string(visiting, depth + 1), value.element_string(visiting, depth + 1)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Object::Function(function) => format!("<fn {}>", function.declaration.display_name()),
+            Object::NativeFunction(function) => format!("<native fn {}>", function.name),
+            Object::Struct(class) => format!("<struct {}>", class.name()),
+            Object::Class(class) => format!("<class {}>", class.name()),
+            Object::Instance(instance) => {
+                let instance = instance.borrow();
+                let fields: Vec<String> = instance.fields().iter()
+                    .map(|(field, value)| format!("{}: {}", field, value.element_string(visiting, depth + 1)))
+                    .collect();
+                match fields.is_empty() {
+                    true => format!("{} {{}}", instance.class.name()),
+                    false => format!("{} {{ {} }}", instance.class.name(), fields.join(", ")),
+                }
+            }
+            Object::Enum(enumeration) => format!("<enum {}>", enumeration.name()),
+            Object::Variant(variant) => match variant.enumeration.variant_fields(variant.index) {
+                Some(_) => {
+                    let values: Vec<String> = variant.values.iter().map(|value| value.element_string(visiting, depth + 1)).collect();
+                    format!("{}({})", variant.name(), values.join(", "))
+                }
+                None => variant.name(),
+            },
+            Object::VariantConstructor(enumeration, index) => {
+                format!("<constructor {}.{}>", enumeration.name(), enumeration.variant_name(*index))
+            }
+            Object::BoundMethod(bound) => {
+                let class = match &bound.receiver {
+                    Object::Instance(instance) => instance.borrow().class.name(),
+                    other => other.type_name().to_string(),
+                };
+                format!("<bound method {}.{}>", class, bound.method.declaration.display_name())
+            }
+            Object::Nil => "nil".to_string(),
+        }
+    }
+
+    /// How a value prints inside a collection, strings and chars are quoted so `["1"]` and `[1]` differ.
+    fn element_string(&self, visiting: &mut HashSet<*const ()>, depth: usize) -> String {
+        match self {
+            Object::String(value) => format!("{:?}", value),
+            Object::Char(value) => format!("{:?}", value),
+            _ => self.string_within(visiting, depth),
+        }
+    }
+
+    /// The value with its elements left out, `None` for values without any.
+    fn elided(&self) -> Option<String> {
+        match self {
+            Object::List(_) => Some("[...]".to_string()),
+            Object::Tuple(_) => Some("(...)".to_string()),
+            Object::Map(_) => Some("{...}".to_string()),
+            Object::Instance(instance) => Some(format!("{} {{...}}", instance.borrow().class.name())),
+            Object::Variant(variant) => variant.enumeration.variant_fields(variant.index).map(|_| format!("{}(...)", variant.name())),
+            _ => None,
+        }
+    }
+
+    /// The identity of a mutable container, the only values that can end up holding themselves.
+    pub fn container(&self) -> Option<*const ()> {
+        match self {
+            Object::List(elements) => Some(Rc::as_ptr(elements) as *const ()),
+            Object::Map(map) => Some(Rc::as_ptr(map) as *const ()),
+            Object::Instance(instance) => Some(Rc::as_ptr(instance) as *const ()),
+            _ => None,
+        }
+    }
+
+    /// The type and value, as runtime errors show an offending value, like ``string `a` ``.
+    pub fn describe(&self) -> String {
+        format!("{} `{}`", self.type_name(), self)
+    }
+
+    pub fn new_list(elements: Vec<Object>) -> Object {
+        Object::List(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn new_map(map: Map) -> Object {
+        Object::Map(Rc::new(RefCell::new(map)))
+    }
+
+    /// Demotes to an `Integer` when the value fits.
+    pub fn from_bigint(integer: BigInt) -> Object {
+        match integer.to_i64() {
+            Some(integer) => Object::Integer(integer),
+            None => Object::BigInt(integer),
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Object::Integer(_) | Object::BigInt(_) | Object::Float(_) | Object::Decimal(_))
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Object::Integer(_) | Object::BigInt(_))
+    }
+
+    /// The value of an integer of either width, `None` for other values.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Object::Integer(value) => Some(BigInt::from_i64(*value)),
+            Object::BigInt(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// The exact value of an integer or decimal, `None` for other values.
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        match self {
+            Object::Decimal(value) => Some(value.clone()),
+            _ => self.to_bigint().map(Decimal::from_bigint),
+        }
+    }
+
+    /// The value of any numeric type as a float, integers and decimals are rounded to the nearest float.
+    pub fn _get_number(&self) -> Result<Precision, LunalaErrors> {
+        match self {
+            Object::Integer(value) => Ok(*value as Precision),
+            Object::BigInt(value) => Ok(value.to_f64()),
+            Object::Float(value) => Ok(*value),
+            Object::Decimal(value) => Ok(value.to_f64()),
+            _ => Err(LunalaErrors::new(ErrorTypes::ErrorNotANumber(self.get_string(), None), 0))
+        }
+    }
+}
+
+impl std::fmt::Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.get_string())
+    }
+}
@@ -1,18 +1,47 @@
 use crate::errors::{ErrorTypes, LunalaErrors};
 use crate::expressions::ExpType::{Binary, Grouping, Unary};
 use crate::expressions::{ExpType, Literal};
+use crate::pattern::{MatchArm, Pattern};
 use crate::statement;
-use crate::statement::Statement;
+use crate::statement::{EnumVariant, Statement};
 use crate::tokens::{Token, TokenType};
+use std::rc::Rc;
+
+/// How deep expressions, statements and patterns nest inside each other before parsing fails,
+/// so neither the parser nor the interpreter recurse far enough to overflow the stack.
+const MAX_NESTING: usize = 64;
+
+/// An enum's name with the names of its variants.
+pub type EnumVariants = (String, Vec<String>);
 
 pub struct Parser {
     tokens: Vec<Token>,
     cursor: usize,
+    // Number of loops enclosing the statement being parsed, `break` and `continue` need at least one
+    loop_depth: usize,
+    // Number of functions enclosing the statement being parsed, `return` needs at least one
+    function_depth: usize,
+    // Number of expressions, statements and patterns enclosing the one being parsed
+    nesting: usize,
+    // Whether a name followed by `{` is a struct literal, it isn't in a `match` subject outside of brackets
+    struct_literals: bool,
+    // One entry per class enclosing the statement being parsed, whether it has a superclass.
+    // `self` needs at least one, `super` needs the innermost to have a superclass
+    classes: Vec<bool>,
+    // Enums declared in this or an earlier source, and every `match` with its arms and whether
+    // they are guarded, checked for exhaustiveness once the whole source is parsed
+    enums: Vec<EnumVariants>,
+    matches: Vec<(Token, Vec<(Pattern, bool)>)>,
+    warnings: Vec<String>,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens, cursor: 0 }
+    /// `enums` are the ones declared by earlier sources run in the same interpreter, see [`Parser::enums`].
+    pub fn new(tokens: Vec<Token>, enums: Vec<EnumVariants>) -> Parser {
+        Parser {
+            tokens, cursor: 0, loop_depth: 0, function_depth: 0, nesting: 0, struct_literals: true, classes: Vec::new(),
+            enums, matches: Vec::new(), warnings: Vec::new(),
+        }
     }
 
     pub fn _get_tokens(&self) -> Vec<Token> {
@@ -22,21 +51,492 @@ impl Parser {
     pub fn parse(&mut self) -> Result<statement::Statements, LunalaErrors> {
         let mut statements = Vec::new();
         while !self.at_end()? {
-            statements.push(self.statement()?);
+            statements.push(self.declaration()?);
         }
+        self.check_exhaustive();
         Ok(statements)
     }
 
+    /// Problems that don't stop the source from running, found by the last `parse`.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Every enum known after the last `parse`, to hand to the parser of the next source.
+    pub fn enums(&self) -> &[EnumVariants] {
+        &self.enums
+    }
+
+    /// Warns about every `match` over a known enum that doesn't cover all of its variants, the latest declaration wins.
+    /// Only arms without a guard count, and a variant is only covered when all of its fields are matched by anything.
+    fn check_exhaustive(&mut self) {
+        for (keyword, arms) in &self.matches {
+            if arms.iter().any(|(pattern, guarded)| !guarded && pattern.is_irrefutable()) {
+                continue;
+            }
+            let enum_name = arms.iter().find_map(|(pattern, _)| match pattern {
+                Pattern::Variant { enum_name, .. } => Some(enum_name.access_lexeme()),
+                _ => None,
+            });
+            let Some((name, variants)) = self.enums.iter().rev().find(|(name, _)| Some(name) == enum_name.as_ref()) else { continue };
+            let missing: Vec<String> = variants.iter()
+                .filter(|variant| !arms.iter().any(|(pattern, guarded)| match pattern {
+                    Pattern::Variant { enum_name, variant: covered, fields } => {
+                        !guarded && enum_name.access_lexeme() == *name && covered.access_lexeme() == **variant
+                            && fields.iter().flatten().all(|field| field.is_irrefutable())
+                    }
+                    _ => false,
+                }))
+                .map(|variant| format!("{}.{}", name, variant))
+                .collect();
+            if !missing.is_empty() {
+                self.warnings.push(format!("`match` at line {} doesn't cover {}", keyword.line(), missing.join(", ")));
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> Result<Statement, LunalaErrors> {
+        match self.peek()?.token_type() {
+            TokenType::Let => {
+                self.advance()?;
+                self.let_declaration()
+            }
+            // `fn` without a name starts an anonymous function expression instead
+            TokenType::Function if self.peek_next()?.token_type() == TokenType::Identifier => {
+                self.advance()?;
+                self.function_declaration()
+            }
+            TokenType::Struct => {
+                self.advance()?;
+                self.struct_declaration()
+            }
+            TokenType::Class => {
+                self.advance()?;
+                self.class_declaration()
+            }
+            TokenType::Enum => {
+                self.advance()?;
+                self.enum_declaration()
+            }
+            _ => self.statement()
+        }
+    }
+
+    fn let_declaration(&mut self) -> Result<Statement, LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        let pattern = self.pattern()?;
+        self.consume(TokenType::Equals, "Expected a `=` after variable name.")?;
+        let initializer = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected a `;` after variable declaration.")?;
+        Ok(Statement::Let(Statement::new_let(keyword, pattern, initializer)))
+    }
+
+    fn struct_declaration(&mut self) -> Result<Statement, LunalaErrors> {
+        let name = self.consume(TokenType::Identifier, "Expected a struct name after `struct`.")?.clone();
+        self.consume(TokenType::LeftCurlyBracket, "Expected a `{` after struct name.")?;
+        let mut fields: Vec<Token> = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? {
+            let field = self.consume(TokenType::Identifier, "Expected a field name.")?.clone();
+            if fields.iter().any(|other| other.access_lexeme() == field.access_lexeme()) {
+                return Err(LunalaErrors::new(ErrorTypes::DuplicateField(name.access_lexeme(), field.access_lexeme()), field.line()));
+            }
+            fields.push(field);
+            if !self.check(TokenType::Comma)? { break; }
+            self.advance()?;
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after struct fields.")?;
+        Ok(Statement::Struct(Rc::new(Statement::new_struct(name, fields))))
+    }
+
+    fn class_declaration(&mut self) -> Result<Statement, LunalaErrors> {
+        let name = self.consume(TokenType::Identifier, "Expected a class name after `class`.")?.clone();
+        let superclass = match self.check(TokenType::LessThan)? {
+            true => {
+                self.advance()?;
+                Some(self.consume(TokenType::Identifier, "Expected a superclass name after `<`.")?.clone())
+            }
+            false => None,
+        };
+        self.consume(TokenType::LeftCurlyBracket, "Expected a `{` after class name.")?;
+
+        self.classes.push(superclass.is_some());
+        let methods = self.methods();
+        self.classes.pop();
+
+        Ok(Statement::Class(Statement::new_class(name, superclass, methods?)))
+    }
+
+    /// Parses the `fn` methods of a class body up to and including its closing `}`.
+    fn methods(&mut self) -> Result<Vec<Rc<statement::FunctionStatement>>, LunalaErrors> {
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? && !self.at_end()? {
+            self.consume(TokenType::Function, "Expected a `fn` method in class body.")?;
+            let name = self.consume(TokenType::Identifier, "Expected a method name after `fn`.")?.clone();
+            methods.push(self.function(Some(name))?);
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after class body.")?;
+        Ok(methods)
+    }
+
+    fn enum_declaration(&mut self) -> Result<Statement, LunalaErrors> {
+        let name = self.consume(TokenType::Identifier, "Expected an enum name after `enum`.")?.clone();
+        self.consume(TokenType::LeftCurlyBracket, "Expected a `{` after enum name.")?;
+        let mut variants: Vec<EnumVariant> = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? {
+            let variant = self.consume(TokenType::Identifier, "Expected a variant name.")?.clone();
+            if variants.iter().any(|other| other.name().access_lexeme() == variant.access_lexeme()) {
+                return Err(LunalaErrors::new(ErrorTypes::DuplicateVariant(name.access_lexeme(), variant.access_lexeme()), variant.line()));
+            }
+            let fields = match self.check(TokenType::LeftBracket)? {
+                true => {
+                    self.advance()?;
+                    let mut fields = Vec::new();
+                    while !self.check(TokenType::RightBracket)? {
+                        fields.push(self.consume(TokenType::Identifier, "Expected a field name.")?.clone());
+                        if !self.check(TokenType::Comma)? { break; }
+                        self.advance()?;
+                    }
+                    self.consume(TokenType::RightBracket, "Expected a `)` after variant fields.")?;
+                    Some(fields)
+                }
+                false => None,
+            };
+            variants.push(EnumVariant::new(variant, fields));
+            if !self.check(TokenType::Comma)? { break; }
+            self.advance()?;
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after enum variants.")?;
+        self.enums.push((name.access_lexeme(), variants.iter().map(|variant| variant.name().access_lexeme()).collect()));
+        Ok(Statement::Enum(Rc::new(Statement::new_enum(name, variants))))
+    }
+
+    /// Parses `(subject) { pattern if guard => body, ... }`, the `match` keyword has already been consumed.
+    /// Arms of a `match` statement are statements, those of a `match` expression are expressions.
+    fn match_arms<T>(&mut self, body: fn(&mut Self) -> Result<T, LunalaErrors>) -> Result<(Token, ExpType, Vec<MatchArm<T>>), LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        // The subject needs no brackets, `match value {` opens the arms rather than a struct literal
+        let struct_literals = std::mem::replace(&mut self.struct_literals, false);
+        let subject = self.nested(Self::assignment);
+        self.struct_literals = struct_literals;
+        let subject = subject?;
+        self.consume(TokenType::LeftCurlyBracket, "Expected a `{` before match arms.")?;
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? && !self.at_end()? {
+            let pattern = self.pattern()?;
+            let guard = match self.check(TokenType::If)? {
+                true => {
+                    self.advance()?;
+                    Some(self.expression()?)
+                }
+                false => None,
+            };
+            self.consume(TokenType::FatArrow, "Expected a `=>` after match pattern.")?;
+            arms.push(MatchArm::new(pattern, guard, body(self)?));
+            if self.check(TokenType::Comma)? {
+                self.advance()?;
+            }
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after match arms.")?;
+        self.matches.push((keyword.clone(), arms.iter().map(|arm| (arm.pattern().clone(), arm.guard().is_some())).collect()));
+        Ok((keyword, subject, arms))
+    }
+
+    /// Parses a pattern, what a `let` binds to or a `match` arm tests.
+    fn pattern(&mut self) -> Result<Pattern, LunalaErrors> {
+        match self.peek()?.token_type() {
+            TokenType::Identifier if self.peek()?.access_lexeme() == "_" => {
+                self.advance()?;
+                Ok(Pattern::Wildcard)
+            }
+            TokenType::Identifier if self.peek_next()?.token_type() == TokenType::Dot => {
+                let enum_name = self.advance()?.clone();
+                self.advance()?;
+                let variant = self.consume(TokenType::Identifier, "Expected a variant name after `.`.")?.clone();
+                let fields = match self.check(TokenType::LeftBracket)? {
+                    true => {
+                        self.advance()?;
+                        let mut fields = Vec::new();
+                        while !self.check(TokenType::RightBracket)? {
+                            fields.push(self.nested(Self::pattern)?);
+                            if !self.check(TokenType::Comma)? { break; }
+                            self.advance()?;
+                        }
+                        self.consume(TokenType::RightBracket, "Expected a `)` after variant patterns.")?;
+                        Some(fields)
+                    }
+                    false => None,
+                };
+                Ok(Pattern::Variant { enum_name, variant, fields })
+            }
+            TokenType::Identifier if self.peek_next()?.token_type() == TokenType::LeftCurlyBracket => {
+                let name = self.advance()?.clone();
+                self.advance()?;
+                Ok(Pattern::Struct { name, fields: self.field_patterns()? })
+            }
+            TokenType::Integer | TokenType::Float | TokenType::Decimal | TokenType::Minus
+            | TokenType::String | TokenType::Char | TokenType::True | TokenType::False | TokenType::Nil => {
+                let start = self.pattern_literal()?;
+                match self.peek()?.token_type() {
+                    TokenType::DotDot | TokenType::DotDotEquals => {
+                        let inclusive = self.advance()?.token_type() == TokenType::DotDotEquals;
+                        let end = self.pattern_literal()?;
+                        Ok(Pattern::Range { start, end, inclusive })
+                    }
+                    _ => Ok(Pattern::Literal(start)),
+                }
+            }
+            TokenType::LeftBracket => {
+                self.advance()?;
+                let mut elements = Vec::new();
+                let mut trailing_comma = false;
+                while !self.check(TokenType::RightBracket)? {
+                    elements.push(self.nested(Self::pattern)?);
+                    trailing_comma = self.check(TokenType::Comma)?;
+                    if !trailing_comma { break; }
+                    self.advance()?;
+                }
+                self.consume(TokenType::RightBracket, "Expected a `)` after tuple pattern.")?;
+                // `(a)` is only parenthesised, like a grouping, `(a,)` is a tuple of one
+                match (elements.len(), trailing_comma) {
+                    (1, false) => Ok(elements.remove(0)),
+                    _ => Ok(Pattern::Tuple(elements)),
+                }
+            }
+            TokenType::LeftSquareBracket => {
+                self.advance()?;
+                let mut elements = Vec::new();
+                let mut rest = None;
+                while !self.check(TokenType::RightSquareBracket)? {
+                    if self.check(TokenType::DotDot)? {
+                        self.advance()?;
+                        rest = match self.check(TokenType::Identifier)? {
+                            true => Some(Some(self.advance()?.clone())),
+                            false => Some(None),
+                        };
+                        break;
+                    }
+                    elements.push(self.nested(Self::pattern)?);
+                    if !self.check(TokenType::Comma)? { break; }
+                    self.advance()?;
+                }
+                self.consume(TokenType::RightSquareBracket, "Expected a `]` after list pattern, `..rest` has to come last.")?;
+                Ok(Pattern::List { elements, rest })
+            }
+            TokenType::LeftCurlyBracket => {
+                self.advance()?;
+                Ok(Pattern::Map(self.field_patterns()?))
+            }
+            _ => Ok(Pattern::Binding(self.consume(TokenType::Identifier, "Expected a variable name or pattern.")?.clone())),
+        }
+    }
+
+    /// Parses `name: pattern` pairs up to and including the closing `}`, a lone `name` binds the field to itself.
+    fn field_patterns(&mut self) -> Result<Vec<(Token, Pattern)>, LunalaErrors> {
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? {
+            let key = self.consume(TokenType::Identifier, "Expected a field name in pattern.")?.clone();
+            let pattern = match self.check(TokenType::Colon)? {
+                true => {
+                    self.advance()?;
+                    self.nested(Self::pattern)?
+                }
+                false => Pattern::Binding(key.clone()),
+            };
+            fields.push((key, pattern));
+            if !self.check(TokenType::Comma)? { break; }
+            self.advance()?;
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after field patterns.")?;
+        Ok(fields)
+    }
+
+    /// A literal in a pattern, numbers may be negative.
+    fn pattern_literal(&mut self) -> Result<Literal, LunalaErrors> {
+        let token = self.advance()?.clone();
+        match token.token_type() {
+            TokenType::True => Ok(Literal::Bool(true)),
+            TokenType::False => Ok(Literal::Bool(false)),
+            TokenType::Nil => Ok(Literal::Nil),
+            TokenType::String => Ok(Literal::String(token.access_lexeme())),
+            TokenType::Char => Ok(Literal::Char(token.access_lexeme().chars().next().unwrap_or_default())),
+            TokenType::Minus if matches!(self.peek()?.token_type(), TokenType::Integer | TokenType::Float | TokenType::Decimal) => {
+                let number = self.advance()?;
+                let negative = Token::new(number.token_type(), Some(format!("-{}", number.access_lexeme())), number.line());
+                Literal::new_number(&negative)
+            }
+            TokenType::Integer | TokenType::Float | TokenType::Decimal => Literal::new_number(&token),
+            _ => Err(LunalaErrors::new(ErrorTypes::ExpressionExpected(token.to_string()), token.line())),
+        }
+    }
+
     fn statement(&mut self) -> Result<Statement, LunalaErrors> {
         match self.peek()?.token_type() {
             TokenType::Print => {
                 self.advance()?;
                 self.print_statement()
             }
+            TokenType::LeftCurlyBracket => {
+                self.advance()?;
+                Ok(Statement::Block(Statement::new_block(self.block()?)))
+            }
+            TokenType::If => {
+                self.advance()?;
+                self.if_statement()
+            }
+            TokenType::While => {
+                self.advance()?;
+                self.while_statement()
+            }
+            TokenType::For => {
+                self.advance()?;
+                self.for_statement()
+            }
+            TokenType::Break | TokenType::Continue => {
+                self.advance()?;
+                self.jump_statement()
+            }
+            TokenType::Return => {
+                self.advance()?;
+                self.return_statement()
+            }
+            TokenType::Match => {
+                self.advance()?;
+                let (keyword, subject, arms) = self.match_arms(|parser| parser.nested(Self::statement))?;
+                Ok(Statement::Match(Statement::new_match(keyword, subject, arms)))
+            }
             _ => self.expression_statement()
         }
     }
 
+    fn function_declaration(&mut self) -> Result<Statement, LunalaErrors> {
+        let name = self.consume(TokenType::Identifier, "Expected a function name after `fn`.")?.clone();
+        Ok(Statement::Function(self.function(Some(name))?))
+    }
+
+    /// Parses the parameters and body of a function, everything after its name.
+    fn function(&mut self, name: Option<Token>) -> Result<Rc<statement::FunctionStatement>, LunalaErrors> {
+        self.consume(TokenType::LeftBracket, "Expected a `(` before parameters.")?;
+        let mut params = Vec::new();
+        if !self.check(TokenType::RightBracket)? {
+            loop {
+                params.push(self.consume(TokenType::Identifier, "Expected a parameter name.")?.clone());
+                if !self.check(TokenType::Comma)? { break; }
+                self.advance()?;
+            }
+        }
+        self.consume(TokenType::RightBracket, "Expected a `)` after parameters.")?;
+        self.consume(TokenType::LeftCurlyBracket, "Expected a `{` before function body.")?;
+
+        // Loops around the declaration don't extend into its body
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        self.loop_depth = loop_depth;
+
+        Ok(Rc::new(Statement::new_function(name, params, body?)))
+    }
+
+    /// Parses the statements of a block, the opening `{` has already been consumed.
+    fn block(&mut self) -> Result<statement::Statements, LunalaErrors> {
+        let mut statements = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? && !self.at_end()? {
+            statements.push(self.nested(Self::declaration)?);
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after block.")?;
+        Ok(statements)
+    }
+
+    fn if_statement(&mut self) -> Result<Statement, LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        self.consume(TokenType::LeftBracket, "Expected a `(` after `if`.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightBracket, "Expected a `)` after if condition.")?;
+
+        let then_branch = self.nested(Self::statement)?;
+        // An `else if` chain is an `if` statement as the else branch, the dangling `else` binds to the nearest `if`
+        let else_branch = match self.check(TokenType::Else)? {
+            true => {
+                self.advance()?;
+                Some(self.nested(Self::statement)?)
+            }
+            false => None,
+        };
+        Ok(Statement::If(Statement::new_if(keyword, condition, then_branch, else_branch)))
+    }
+
+    fn while_statement(&mut self) -> Result<Statement, LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        self.consume(TokenType::LeftBracket, "Expected a `(` after `while`.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightBracket, "Expected a `)` after while condition.")?;
+
+        let body = self.loop_body()?;
+        Ok(Statement::While(Statement::new_while(keyword, condition, body)))
+    }
+
+    fn for_statement(&mut self) -> Result<Statement, LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        self.consume(TokenType::LeftBracket, "Expected a `(` after `for`.")?;
+
+        let initializer = match self.peek()?.token_type() {
+            TokenType::Semicolon => {
+                self.advance()?;
+                None
+            }
+            TokenType::Let => {
+                self.advance()?;
+                Some(self.let_declaration()?)
+            }
+            _ => Some(self.expression_statement()?)
+        };
+        let condition = match self.check(TokenType::Semicolon)? {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::Semicolon, "Expected a `;` after for condition.")?;
+        let increment = match self.check(TokenType::RightBracket)? {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::RightBracket, "Expected a `)` after for clauses.")?;
+
+        let body = self.loop_body()?;
+        Ok(Statement::For(Statement::new_for(keyword, initializer, condition, increment, body)))
+    }
+
+    fn loop_body(&mut self) -> Result<Statement, LunalaErrors> {
+        self.loop_depth += 1;
+        let body = self.nested(Self::statement);
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn jump_statement(&mut self) -> Result<Statement, LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        if self.loop_depth == 0 {
+            return Err(LunalaErrors::new(ErrorTypes::JumpOutsideLoop(keyword.token_type().to_string().to_lowercase()), keyword.line()));
+        }
+        self.consume(TokenType::Semicolon, "Expected a `;` after jump.")?;
+        match keyword.token_type() {
+            TokenType::Break => Ok(Statement::Break(keyword)),
+            _ => Ok(Statement::Continue(keyword)),
+        }
+    }
+
+    fn return_statement(&mut self) -> Result<Statement, LunalaErrors> {
+        let keyword = self.previous()?.clone();
+        if self.function_depth == 0 {
+            return Err(LunalaErrors::new(ErrorTypes::ReturnOutsideFunction, keyword.line()));
+        }
+        let value = match self.check(TokenType::Semicolon)? {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        self.consume(TokenType::Semicolon, "Expected a `;` after return value.")?;
+        Ok(Statement::Return(Statement::new_return(keyword, value)))
+    }
+
     fn print_statement(&mut self) -> Result<Statement, LunalaErrors> {
         let expression = self.expression()?;
         self.consume(TokenType::Semicolon, "Expected a `;` after value.")?;
@@ -49,8 +549,92 @@ impl Parser {
         Ok(Statement::Expression(Statement::new_expression(expression)))
     }
 
+    /// Any expression, also inside the brackets of a `match` subject, so struct literals are allowed again.
     fn expression(&mut self) -> Result<ExpType, LunalaErrors> {
-        self.equality()
+        let struct_literals = std::mem::replace(&mut self.struct_literals, true);
+        let expression = self.nested(Self::assignment);
+        self.struct_literals = struct_literals;
+        expression
+    }
+
+    fn assignment(&mut self) -> Result<ExpType, LunalaErrors> {
+        let expression = self.coalesce()?;
+
+        match self.peek()?.token_type() {
+            TokenType::Equals | TokenType::PlusEquals | TokenType::MinusEquals
+            | TokenType::StarEquals | TokenType::SlashEquals | TokenType::PercentEquals => {
+                self.advance()?;
+                let operator = self.previous()?.clone();
+                // Recurse so that `a = b = c` groups as `a = (b = c)`
+                let value = Box::from(self.nested(Self::assignment)?);
+                match expression {
+                    ExpType::Variable { name } => Ok(ExpType::Assign { name, operator, value }),
+                    ExpType::Index { object, bracket, index } => Ok(ExpType::IndexAssign { object, bracket, index, operator, value }),
+                    ExpType::Get { object, name } => Ok(ExpType::Set { object, name, operator, value }),
+                    _ => Err(LunalaErrors::new(ErrorTypes::InvalidAssignmentTarget(expression.to_string()), operator.line())),
+                }
+            }
+            _ => Ok(expression)
+        }
+    }
+
+    /// `value ?? fallback`, the fallback is only evaluated when the value is `nil`.
+    fn coalesce(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.or()?;
+        // Each operator chained on nests the expression so far one level deeper
+        let nesting = self.nesting;
+        while self.check(TokenType::DoubleQuestion)? {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.or()?);
+            expression = ExpType::Logical {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
+    fn or(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.and()?;
+        let nesting = self.nesting;
+        while self.check(TokenType::Or)? {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.and()?);
+            expression = ExpType::Logical {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.equality()?;
+        let nesting = self.nesting;
+        while self.check(TokenType::And)? {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.equality()?);
+            expression = ExpType::Logical {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
     }
 
     fn equality(&mut self) -> Result<ExpType, LunalaErrors> {
@@ -73,13 +657,13 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<ExpType, LunalaErrors> {
-        let mut expression = self.term();
+        let mut expression = self.bit_or();
 
         match self.peek()?.token_type() {
             TokenType::LessThan | TokenType::LessEquals | TokenType::GreaterThan | TokenType::GreaterEquals => {
                 self.advance()?;
                 let operator = self.previous()?.clone();
-                let right = Box::from(self.term()?);
+                let right = Box::from(self.bit_or()?);
                 expression = Ok(Binary {
                     operator, right,
                     left: Box::from(expression?),
@@ -90,11 +674,89 @@ impl Parser {
         expression
     }
 
+    /// The bitwise operators bind tighter than comparisons, so `flags & MASK == 0` tests the masked bits.
+    fn bit_or(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.bit_xor()?;
+        let nesting = self.nesting;
+        while self.check(TokenType::Pipe)? {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.bit_xor()?);
+            expression = Binary {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
+    fn bit_xor(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.bit_and()?;
+        let nesting = self.nesting;
+        while self.check(TokenType::Caret)? {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.bit_and()?);
+            expression = Binary {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
+    fn bit_and(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.shift()?;
+        let nesting = self.nesting;
+        while self.check(TokenType::Ampersand)? {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.shift()?);
+            expression = Binary {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
+    fn shift(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.term()?;
+        let nesting = self.nesting;
+        while matches!(self.peek()?.token_type(), TokenType::ShiftLeft | TokenType::ShiftRight) {
+            self.advance()?;
+            self.deeper()?;
+            let operator = self.previous()?.clone();
+            let right = Box::from(self.term()?);
+            expression = Binary {
+                operator,
+                right,
+                left: Box::from(expression),
+            };
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
     fn term(&mut self) -> Result<ExpType, LunalaErrors> {
         let mut expression = self.factor()?;
-
+        let nesting = self.nesting;
         while matches!(self.peek()?.token_type(), TokenType::Plus | TokenType::Minus) {
             self.advance()?;
+            self.deeper()?;
             let operator = self.previous()?.clone();
             let right = Box::from(self.factor()?);
             expression = Binary {
@@ -103,15 +765,17 @@ impl Parser {
                 left: Box::from(expression),
             };
         }
+        self.nesting = nesting;
 
         Ok(expression)
     }
 
     fn factor(&mut self) -> Result<ExpType, LunalaErrors> {
         let mut expression = self.unary()?;
-    
-        while matches!(self.peek()?.token_type(), TokenType::Slash | TokenType::Star) {
+        let nesting = self.nesting;
+        while matches!(self.peek()?.token_type(), TokenType::Slash | TokenType::Star | TokenType::Percent) {
             self.advance()?;
+            self.deeper()?;
             let operator = self.previous()?.clone();
             let right = Box::from(self.unary()?);
             expression = Binary {
@@ -120,24 +784,95 @@ impl Parser {
                 left: Box::from(expression),
             };
         }
+        self.nesting = nesting;
     
         Ok(expression)
     }
 
     fn unary(&mut self) -> Result<ExpType, LunalaErrors> {
         match self.peek()?.token_type() {
-            TokenType::Minus | TokenType::Bang => {
+            TokenType::Minus | TokenType::Bang | TokenType::Tilde => {
                 self.advance()?;
                 let operator = self.previous()?.clone();
                 //println!("Unary: [{}, {}]", operator.clone(), self.peek()?);
-                Ok(Unary {operator, expression: Box::from(self.unary()?) })
+                Ok(Unary {operator, expression: Box::from(self.nested(Self::unary)?) })
             },
             _ => {
-                self.primary()
+                self.power()
             }
         }
     }
 
+    fn power(&mut self) -> Result<ExpType, LunalaErrors> {
+        let expression = self.call()?;
+
+        if self.check(TokenType::DoubleStar)? {
+            self.advance()?;
+            let operator = self.previous()?.clone();
+            // The exponent may carry its own sign and recurses, so `2 ** -1` works and `2 ** 3 ** 2` is `2 ** (3 ** 2)`
+            let right = Box::from(self.nested(Self::unary)?);
+            return Ok(Binary {
+                operator,
+                right,
+                left: Box::from(expression),
+            });
+        }
+
+        Ok(expression)
+    }
+
+    /// Calls, indexing and field access chain left to right, like `table[0](x)[1]`.
+    /// After a `?.` on `nil` the rest of the chain is skipped, so `user?.address.city` is `nil` without a user.
+    fn call(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut expression = self.primary()?;
+        let nesting = self.nesting;
+        loop {
+            match self.peek()?.token_type() {
+                TokenType::LeftBracket => {
+                    self.advance()?;
+                    self.deeper()?;
+                    let arguments = self.arguments(TokenType::RightBracket)?;
+                    let paren = self.consume(TokenType::RightBracket, "Expected a `)` after arguments.")?.clone();
+                    expression = ExpType::Call { callee: Box::from(expression), paren, arguments };
+                }
+                TokenType::LeftSquareBracket => {
+                    self.advance()?;
+                    self.deeper()?;
+                    let index = Box::from(self.expression()?);
+                    let bracket = self.consume(TokenType::RightSquareBracket, "Expected a `]` after index.")?.clone();
+                    expression = ExpType::Index { object: Box::from(expression), bracket, index };
+                }
+                TokenType::Dot => {
+                    self.advance()?;
+                    self.deeper()?;
+                    let name = self.consume(TokenType::Identifier, "Expected a field name after `.`.")?.clone();
+                    expression = ExpType::Get { object: Box::from(expression), name };
+                }
+                TokenType::QuestionDot => {
+                    self.advance()?;
+                    self.deeper()?;
+                    let name = self.consume(TokenType::Identifier, "Expected a field name after `?.`.")?.clone();
+                    expression = ExpType::OptionalGet { object: Box::from(expression), name };
+                }
+                _ => break,
+            }
+        }
+        self.nesting = nesting;
+
+        Ok(expression)
+    }
+
+    /// Comma separated expressions up to, but not including, `closing`. A trailing comma is allowed.
+    fn arguments(&mut self, closing: TokenType) -> Result<Vec<ExpType>, LunalaErrors> {
+        let mut arguments = Vec::new();
+        while !self.check(closing.clone())? {
+            arguments.push(self.expression()?);
+            if !self.check(TokenType::Comma)? { break; }
+            self.advance()?;
+        }
+        Ok(arguments)
+    }
+
     fn primary(&mut self) -> Result<ExpType, LunalaErrors> {
         //println!("Literal [{}]", self.peek()?);
         let expression = match self.peek()?.token_type() {
@@ -147,7 +882,10 @@ impl Parser {
             TokenType::False => { 
                 self.advance()?; 
                 ExpType::Literal(Literal::Bool(false)) },
-            TokenType::Number => {
+            TokenType::Nil => {
+                self.advance()?;
+                ExpType::Literal(Literal::Nil) },
+            TokenType::Integer | TokenType::Float | TokenType::Decimal => {
                 self.advance()?;
                 ExpType::Literal(Literal::new_number(self.previous()?)?)
             },
@@ -155,25 +893,185 @@ impl Parser {
                 self.advance()?;
                 ExpType::Literal(Literal::String(self.previous()?.access_lexeme()))
             },
+            TokenType::Char => {
+                self.advance()?;
+                let value = self.previous()?.access_lexeme().chars().next().unwrap_or_default();
+                ExpType::Literal(Literal::Char(value))
+            },
+            // Conditions are parenthesised, so outside a `match` subject a name directly followed by `{` constructs a struct
+            TokenType::Identifier if self.struct_literals && self.peek_next()?.token_type() == TokenType::LeftCurlyBracket => {
+                let name = self.advance()?.clone();
+                self.advance()?;
+                self.struct_literal(name)?
+            },
+            TokenType::Identifier => {
+                self.advance()?;
+                ExpType::Variable { name: self.previous()?.clone() }
+            },
+            TokenType::Function => {
+                self.advance()?;
+                ExpType::Function(self.function(None)?)
+            },
+            TokenType::Match => {
+                self.advance()?;
+                let (keyword, subject, arms) = self.match_arms(Self::expression)?;
+                ExpType::Match { keyword, subject: Box::from(subject), arms }
+            },
+            TokenType::SelfKeyword => {
+                let keyword = self.advance()?.clone();
+                if self.classes.is_empty() {
+                    return Err(LunalaErrors::new(ErrorTypes::SelfOutsideMethod, keyword.line()));
+                }
+                ExpType::SelfValue { keyword }
+            },
+            TokenType::Super => {
+                let keyword = self.advance()?.clone();
+                if self.classes.last() != Some(&true) {
+                    return Err(LunalaErrors::new(ErrorTypes::SuperOutsideSubclass, keyword.line()));
+                }
+                self.consume(TokenType::Dot, "Expected a `.` after `super`.")?;
+                let method = self.consume(TokenType::Identifier, "Expected a method name after `super.`.")?.clone();
+                ExpType::Super { keyword, method }
+            },
+            TokenType::AltQuote => {
+                self.advance()?;
+                self.template()?
+            },
+            TokenType::LeftSquareBracket => {
+                self.advance()?;
+                let elements = self.arguments(TokenType::RightSquareBracket)?;
+                self.consume(TokenType::RightSquareBracket, "Expected a `]` after list elements.")?;
+                ExpType::List { elements }
+            },
+            // A `{` in statement position opens a block, anywhere else it's a map
+            TokenType::LeftCurlyBracket => {
+                self.advance()?;
+                self.map()?
+            },
             TokenType::LeftBracket => {
                 self.advance()?;
+                if self.check(TokenType::RightBracket)? {
+                    self.advance()?;
+                    return Ok(ExpType::Tuple { elements: Vec::new() });
+                }
                 let expression = self.expression()?;
+                // A comma makes it a tuple, `(1,)` is a tuple of one and `(1)` just a grouping
+                if self.check(TokenType::Comma)? {
+                    self.advance()?;
+                    let mut elements = vec![expression];
+                    elements.append(&mut self.arguments(TokenType::RightBracket)?);
+                    self.consume(TokenType::RightBracket, "Expected a `)` after tuple elements.")?;
+                    return Ok(ExpType::Tuple { elements });
+                }
                 self.consume(TokenType::RightBracket, "Expected a `)` after expression.")?;
                 //println!("Group: [{}]", expression);
                 Grouping { expression: Box::from(expression), }
             },
-            _ => { return Err(LunalaErrors::new(ErrorTypes::ExpressionExpected(self.peek()?.to_string()), self.cursor)) }
+            _ => { return Err(LunalaErrors::new(ErrorTypes::ExpressionExpected(self.peek()?.to_string()), self.line())) }
         };
         Ok(expression)
     }
 
+    /// Parses the `field: value` pairs of a struct literal, the opening `{` has already been consumed.
+    /// A field without a value takes the variable of the same name, `Point { x, y }`.
+    fn struct_literal(&mut self, name: Token) -> Result<ExpType, LunalaErrors> {
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? {
+            let field = self.consume(TokenType::Identifier, "Expected a field name.")?.clone();
+            let value = match self.check(TokenType::Colon)? {
+                true => {
+                    self.advance()?;
+                    self.expression()?
+                }
+                false => ExpType::Variable { name: field.clone() },
+            };
+            fields.push((field, value));
+            if !self.check(TokenType::Comma)? { break; }
+            self.advance()?;
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after struct fields.")?;
+        Ok(ExpType::StructLiteral { name, fields })
+    }
+
+    /// Parses the `key: value` entries of a map literal, the opening `{` has already been consumed.
+    fn map(&mut self) -> Result<ExpType, LunalaErrors> {
+        let brace = self.previous()?.clone();
+        let mut entries = Vec::new();
+        while !self.check(TokenType::RightCurlyBracket)? {
+            let key = self.expression()?;
+            self.consume(TokenType::Colon, "Expected a `:` after map key.")?;
+            entries.push((key, self.expression()?));
+            if !self.check(TokenType::Comma)? { break; }
+            self.advance()?;
+        }
+        self.consume(TokenType::RightCurlyBracket, "Expected a `}` after map entries.")?;
+        Ok(ExpType::Map { brace, entries })
+    }
+
+    /// Parses the parts of a template literal, the opening `` ` `` has already been consumed.
+    fn template(&mut self) -> Result<ExpType, LunalaErrors> {
+        let mut parts = Vec::new();
+        let quote = loop {
+            match self.peek()?.token_type() {
+                TokenType::AltQuote => {
+                    break self.advance()?.clone();
+                }
+                TokenType::TemplateString => {
+                    self.advance()?;
+                    parts.push(ExpType::Literal(Literal::String(self.previous()?.access_lexeme())));
+                }
+                TokenType::InterpolationStart => {
+                    self.advance()?;
+                    parts.push(self.expression()?);
+                    self.consume(TokenType::InterpolationEnd, "Expected a `}` after interpolated expression.")?;
+                }
+                _ => {
+                    return Err(LunalaErrors::new(ErrorTypes::ExpressionExpected(self.peek()?.to_string()), self.line()));
+                }
+            }
+        };
+        Ok(ExpType::Interpolation { quote, parts })
+    }
+
+    /// Parses one level deeper than the caller.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, LunalaErrors>) -> Result<T, LunalaErrors> {
+        let nesting = self.nesting;
+        self.deeper()?;
+        let result = parse(self);
+        self.nesting = nesting;
+        result
+    }
+
+    /// Goes one level deeper, failing once the source nests more than `MAX_NESTING` deep.
+    fn deeper(&mut self) -> Result<(), LunalaErrors> {
+        if self.nesting == MAX_NESTING {
+            return Err(LunalaErrors::new(ErrorTypes::NestingTooDeep(MAX_NESTING), self.line()));
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
     fn peek(&self) -> Result<&Token, LunalaErrors> {
         match self.tokens.get(self.cursor) {
             Some(token) => { 
                 //println!("Peek: {}", token);
                 Ok(token) 
             },
-            None => Err(LunalaErrors::new(ErrorTypes::InvalidToken(format!("No token at cursor[{}]", self.cursor)), self.cursor))
+            None => Err(LunalaErrors::new(ErrorTypes::InvalidToken(format!("No token at cursor[{}]", self.cursor)), self.line()))
+        }
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.cursor).or(self.tokens.last()) {
+            Some(token) => token.line(),
+            None => 0,
+        }
+    }
+
+    fn peek_next(&self) -> Result<&Token, LunalaErrors> {
+        match self.tokens.get(self.cursor + 1) {
+            Some(token) => Ok(token),
+            None => self.peek(),
         }
     }
 
@@ -197,12 +1095,12 @@ impl Parser {
 
     fn previous(&mut self) -> Result<&Token, LunalaErrors> {
         if self.cursor == 0 {
-            return Err(LunalaErrors::new(ErrorTypes::NoPreviousItem(0), self.cursor));
+            return Err(LunalaErrors::new(ErrorTypes::NoPreviousItem(0), self.line()));
         }
         
         match self.tokens.get(self.cursor - 1) {
             None => {
-                Err(LunalaErrors::new(ErrorTypes::NoPreviousItem(self.cursor - 1), self.cursor))
+                Err(LunalaErrors::new(ErrorTypes::NoPreviousItem(self.cursor - 1), self.line()))
             }
             Some(token) => {
                 Ok(token)
@@ -216,7 +1114,7 @@ impl Parser {
         }
         Err(LunalaErrors::new(ErrorTypes::Error(
             format!("Consume of `{}` to {} failed `{}`", token_type, self.peek()?, message),
-        ), self.cursor))
+        ), self.line()))
     }
     
     
@@ -0,0 +1,147 @@
+use crate::expressions::{ExpType, Literal};
+use crate::tokens::Token;
+
+/// The left-hand side of a destructuring `let`, like `(a, b)`, `[x, ..rest]` or `{name, age}`,
+/// or what a `match` arm tests its value against.
+/// Patterns nest, so `let (id, [first, ..]) = row;` is allowed.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Binds the whole value to a name.
+    Binding(Token),
+    /// `_`, matches anything without binding it.
+    Wildcard,
+    /// Matches a value equal to the literal.
+    Literal(Literal),
+    /// Matches a number or char within `start..end`, or `start..=end` when `inclusive`.
+    Range {
+        start: Literal,
+        end: Literal,
+        inclusive: bool,
+    },
+    /// Matches a tuple with exactly as many elements.
+    Tuple(Vec<Pattern>),
+    /// Matches a list with exactly as many elements, or at least as many with a `..rest` collecting the others.
+    /// A bare `..` is `Some(None)`, it allows more elements without binding them.
+    List {
+        elements: Vec<Pattern>,
+        rest: Option<Option<Token>>,
+    },
+    /// Matches a map holding every named key or a struct instance with those fields, `{name}` is short for `{name: name}`.
+    Map(Vec<(Token, Pattern)>),
+    /// Matches an instance of the named struct or class whose fields match, `Point { x: 0, y }`.
+    Struct {
+        name: Token,
+        fields: Vec<(Token, Pattern)>,
+    },
+    /// Matches one variant of an enum, `Shape.Rect(w, h)`. Unit variants have no `fields`.
+    Variant {
+        enum_name: Token,
+        variant: Token,
+        fields: Option<Vec<Pattern>>,
+    },
+}
+
+/// One `pattern if guard => body` arm of a `match`, the body is an expression or a statement.
+#[derive(Debug, Clone)]
+pub struct MatchArm<T> {
+    pattern: Pattern,
+    guard: Option<ExpType>,
+    body: T,
+}
+
+impl<T> MatchArm<T> {
+    pub fn new(pattern: Pattern, guard: Option<ExpType>, body: T) -> MatchArm<T> {
+        MatchArm { pattern, guard, body }
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn guard(&self) -> Option<&ExpType> {
+        self.guard.as_ref()
+    }
+
+    pub fn body(&self) -> &T {
+        &self.body
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for MatchArm<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(ref guard) = self.guard { write!(f, " if {}", guard)? }
+        write!(f, " => {}", self.body)
+    }
+}
+
+impl Pattern {
+    /// Whether the pattern matches every value, so a `match` arm with it covers all cases.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Binding(_) | Pattern::Wildcard)
+    }
+
+    fn get_string(&self) -> String {
+        match self {
+            Pattern::Binding(name) => {
+                name.access_lexeme()
+            }
+            Pattern::Wildcard => {
+                "_".to_string()
+            }
+            Pattern::Literal(literal) => {
+                match literal {
+                    Literal::String(value) => format!("{:?}", value),
+                    Literal::Char(value) => format!("{:?}", value),
+                    _ => literal.to_string(),
+                }
+            }
+            Pattern::Range { start, end, inclusive } => {
+                format!("{}{}{}", start, if *inclusive { "..=" } else { ".." }, end)
+            }
+            Pattern::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.get_string()).collect();
+                match elements.len() {
+                    1 => format!("({},)", elements[0]),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            Pattern::List { elements, rest } => {
+                let mut elements: Vec<String> = elements.iter().map(|element| element.get_string()).collect();
+                match rest {
+                    Some(Some(rest)) => elements.push(format!("..{}", rest.access_lexeme())),
+                    Some(None) => elements.push("..".to_string()),
+                    None => {}
+                }
+                format!("[{}]", elements.join(", "))
+            }
+            Pattern::Map(fields) => {
+                let fields: Vec<String> = fields.iter().map(|(key, pattern)| match pattern {
+                    Pattern::Binding(name) if name.access_lexeme() == key.access_lexeme() => key.access_lexeme(),
+                    _ => format!("{}: {}", key.access_lexeme(), pattern),
+                }).collect();
+                format!("{{{}}}", fields.join(", "))
+            }
+            Pattern::Struct { name, fields } => {
+                let fields: Vec<String> = fields.iter().map(|(key, pattern)| format!("{}: {}", key.access_lexeme(), pattern)).collect();
+                format!("{} {{ {} }}", name.access_lexeme(), fields.join(", "))
+            }
+            Pattern::Variant { enum_name, variant, fields } => {
+                let name = format!("{}.{}", enum_name.access_lexeme(), variant.access_lexeme());
+                match fields {
+                    Some(fields) => {
+                        let fields: Vec<String> = fields.iter().map(|field| field.get_string()).collect();
+                        format!("{}({})", name, fields.join(", "))
+                    }
+                    None => name,
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.get_string())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bignum::BigInt;
 use crate::errors::{ErrorTypes, LunalaErrors};
 use crate::tokens::{ReservedKeywords, Token, TokenType};
 
@@ -5,13 +6,16 @@ pub struct Scanner {
     source: Vec<char>,
     tokens: Vec<Token>,
     cursor: usize,
+    line: usize,
+    // Brace depth inside every open `${` of a template literal, innermost last
+    templates: Vec<usize>,
 }
 
 impl Scanner {
     pub fn new(source: &str) -> Scanner {
         let mut s = source.chars().collect::<Vec<char>>();
         s.insert(0, ' ');
-        Scanner { source: s, tokens: Vec::new(), cursor: 0 }
+        Scanner { source: s, tokens: Vec::new(), cursor: 0, line: 1, templates: Vec::new() }
     }
 
     pub fn current(&self) -> Option<&char> {
@@ -20,12 +24,17 @@ impl Scanner {
     
     pub fn advance(&mut self) -> Option<&char> {
         self.cursor += 1;
+        if self.current() == Some(&'\n') { self.line += 1; }
         self.current()
     }
     
     pub fn peek(&self) -> Option<&char> {
        self.source.get(self.cursor + 1)
     }
+
+    pub fn peek_next(&self) -> Option<&char> {
+       self.source.get(self.cursor + 2)
+    }
     
     fn _pop(&self) -> Option<&char> {
         self.source.get(self.cursor)
@@ -41,7 +50,7 @@ impl Scanner {
     }
 
     fn add(&mut self, token_type: TokenType) {
-        self.add_token(Token::new(token_type, None, self.cursor));
+        self.add_token(Token::new(token_type, None, self.line));
     }
 
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, LunalaErrors> {
@@ -56,8 +65,13 @@ impl Scanner {
 
             //println!("c[{}]", current_char);
             
-            if current_char.is_numeric() { self.number(); continue; }
-            if current_char.is_alphabetic() { self.alpha(); continue; }
+            if current_char.is_ascii_digit() { self.number()?; continue; }
+            if current_char == 'r' && self.peek() == Some(&'"') {
+                self.advance();
+                self.string(true)?;
+                continue;
+            }
+            if current_char.is_alphabetic() || current_char == '_' { self.alpha(); continue; }
             
             match (current_char, self.peek()) {
                 ('/', _) => {
@@ -66,68 +80,267 @@ impl Scanner {
                                 self.add(TokenType::Comment);
                                 while self.peek() != Some(&'\n') && !self.at_end() { let _ = self.advance(); }
                         },
+                        Some('=') => {
+                            self.advance();
+                            self.add(TokenType::SlashEquals);
+                        },
                         None | Some(_) => {
                             self.add(TokenType::Slash)
                         }
                     }
                 },
+                ('*', Some('*')) => {
+                    self.advance();
+                    self.add(TokenType::DoubleStar);
+                }
+                ('*', Some('=')) => {
+                    self.advance();
+                    self.add(TokenType::StarEquals);
+                }
                 ('*', _) => { self.add(TokenType::Star) },
                 ('=', Some('=')) => {
                     self.advance();
                     self.add(TokenType::DoubleEquals);
                 },
+                ('=', Some('>')) => {
+                    self.advance();
+                    self.add(TokenType::FatArrow);
+                },
                 ('=', _) => { self.add(TokenType::Equals) },
                 ('<', Some('=')) => {
                     self.advance();
                     self.add(TokenType::LessEquals);
                 }
+                ('<', Some('<')) => {
+                    self.advance();
+                    self.add(TokenType::ShiftLeft);
+                }
                 ('<', _) => { self.add(TokenType::LessThan) },
                 ('>', Some('=')) => {
                     self.advance();
                     self.add(TokenType::GreaterEquals);
                 }
+                ('>', Some('>')) => {
+                    self.advance();
+                    self.add(TokenType::ShiftRight);
+                }
                 ('>', _) => { self.add(TokenType::GreaterThan) },
                 ('!', Some('=') ) => {
                     self.advance();
                     self.add(TokenType::BangEquals);
                 }
                 ('!', _) => { self.add(TokenType::Bang) },
-                ('"', _) => {
-                    let start = self.cursor + 1;
-                    while self.peek() != Some(&'"') && !self.at_end() {
-                        let _  = self.advance();
-                        if self.peek() == Some(&'\n') { let _ = self.advance(); }
-                    }
-                    if self.at_end() {
-                        return self.error(ErrorTypes::UnterminatedString);
-                    }
+                ('&', Some('&')) => {
                     self.advance();
-                    let value: String = self.source[start..self.cursor].iter().collect();
-                    self.add_token(Token::new(TokenType::String, Some(value), self.cursor));
-                },
+                    self.add(TokenType::And);
+                }
+                ('&', _) => { self.add(TokenType::Ampersand) },
+                ('|', Some('|')) => {
+                    self.advance();
+                    self.add(TokenType::Or);
+                }
+                ('|', _) => { self.add(TokenType::Pipe) },
+                ('^', _) => { self.add(TokenType::Caret) },
+                ('~', _) => { self.add(TokenType::Tilde) },
+                ('?', Some('.')) => {
+                    self.advance();
+                    self.add(TokenType::QuestionDot);
+                }
+                ('?', Some('?')) => {
+                    self.advance();
+                    self.add(TokenType::DoubleQuestion);
+                }
+                ('"', _) => { self.string(false)? },
                 (';', _) => { self.add(TokenType::Semicolon) },
+                (',', _) => { self.add(TokenType::Comma) },
+                ('+', Some('=')) => {
+                    self.advance();
+                    self.add(TokenType::PlusEquals);
+                }
                 ('+', _) => { self.add(TokenType::Plus)}
+                ('-', Some('=')) => {
+                    self.advance();
+                    self.add(TokenType::MinusEquals);
+                }
                 ('-', _) => { self.add(TokenType::Minus) },
+                ('.', Some('.')) => {
+                    self.advance();
+                    match self.peek() {
+                        Some('=') => {
+                            self.advance();
+                            self.add(TokenType::DotDotEquals);
+                        }
+                        _ => self.add(TokenType::DotDot),
+                    }
+                }
                 ('.', _) => { self.add(TokenType::Dot) },
-                ('{', _) => { self.add(TokenType::LeftCurlyBracket) },
-                ('}', _) => { self.add(TokenType::RightCurlyBracket) },
+                ('{', _) => {
+                    if let Some(depth) = self.templates.last_mut() { *depth += 1; }
+                    self.add(TokenType::LeftCurlyBracket)
+                },
+                ('}', _) => {
+                    match self.templates.last_mut() {
+                        // Closes the `${` of a template literal, the rest of the template follows
+                        Some(0) => {
+                            self.templates.pop();
+                            self.add(TokenType::InterpolationEnd);
+                            self.template()?;
+                        }
+                        Some(depth) => {
+                            *depth -= 1;
+                            self.add(TokenType::RightCurlyBracket)
+                        }
+                        None => { self.add(TokenType::RightCurlyBracket) }
+                    }
+                },
                 ('[', _) => { self.add(TokenType::LeftSquareBracket) },
                 (']', _) => { self.add(TokenType::RightSquareBracket) },
                 ('(', _) => { self.add(TokenType::LeftBracket) },
                 (')', _) => { self.add(TokenType::RightBracket) },
+                ('%', Some('=')) => {
+                    self.advance();
+                    self.add(TokenType::PercentEquals);
+                }
                 ('%', _) => { self.add(TokenType::Percent) },
                 (':', _) => { self.add(TokenType::Colon) },
-                ('\'', _) => { self.add(TokenType::SingleQuote) },
-                ('`', _) => { self.add(TokenType::AltQuote) }
+                ('\'', _) => { self.char()? },
+                ('`', _) => {
+                    self.add(TokenType::AltQuote);
+                    self.template()?;
+                }
                 _ => { return self.error(ErrorTypes::InvalidToken(current_token_binding.to_string())); },
             };
         }
+        if !self.templates.is_empty() {
+            return self.error(ErrorTypes::UnterminatedTemplate);
+        }
         self.add(TokenType::EOF);
         Ok(self.tokens.clone())
     }
 
-    pub fn error(&self, error_types: ErrorTypes) -> Result<Vec<Token>, LunalaErrors> {
-        Err(LunalaErrors::new(error_types, self.cursor))
+    /// Scans the text of a template literal up to its closing `` ` `` or the next `${`.
+    /// The text becomes a `TemplateString`, an interpolation is scanned as regular tokens
+    /// until the `}` matching its `${`.
+    fn template(&mut self) -> Result<(), LunalaErrors> {
+        let mut value = String::new();
+        loop {
+            match (self.peek(), self.peek_next()) {
+                (None, _) => {
+                    return self.error(ErrorTypes::UnterminatedTemplate);
+                }
+                (Some('`'), _) => {
+                    self.advance();
+                    self.add_template_string(value);
+                    self.add(TokenType::AltQuote);
+                    return Ok(());
+                }
+                (Some('$'), Some('{')) => {
+                    self.advance();
+                    self.advance();
+                    self.add_template_string(value);
+                    self.add(TokenType::InterpolationStart);
+                    self.templates.push(0);
+                    return Ok(());
+                }
+                (Some('\\'), _) => {
+                    self.advance();
+                    let escaped = self.escape()?;
+                    value.push(escaped);
+                }
+                (Some(c), _) => {
+                    value.push(*c);
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    /// Scans a string literal, the cursor is on its opening `"`.
+    /// A raw string, written `r"..."`, keeps backslashes as they are.
+    fn string(&mut self, raw: bool) -> Result<(), LunalaErrors> {
+        let mut value = String::new();
+        loop {
+            match self.advance().cloned() {
+                None => { return self.error(ErrorTypes::UnterminatedString); }
+                Some('"') => { break; }
+                Some('\\') if !raw => {
+                    let escaped = self.escape()?;
+                    value.push(escaped);
+                }
+                Some(c) => { value.push(c); }
+            }
+        }
+        self.add_token(Token::new(TokenType::String, Some(value), self.line));
+        Ok(())
+    }
+
+    /// Scans a character literal like `'a'` or `'\n'`, the cursor is on its opening `'`.
+    fn char(&mut self) -> Result<(), LunalaErrors> {
+        let value = match self.advance().cloned() {
+            None => { return self.error(ErrorTypes::InvalidCharLiteral("'".to_string())); }
+            Some('\'') => { return self.error(ErrorTypes::InvalidCharLiteral("''".to_string())); }
+            Some('\\') => self.escape()?,
+            Some(c) => c,
+        };
+        if self.advance() != Some(&'\'') {
+            let mut literal = format!("'{}", value);
+            while let Some(c) = self.current().cloned() {
+                if c == '\'' || c == '\n' { literal.push(c); break; }
+                literal.push(c);
+                self.advance();
+            }
+            return self.error(ErrorTypes::InvalidCharLiteral(literal));
+        }
+        self.add_token(Token::new(TokenType::Char, Some(value.to_string()), self.line));
+        Ok(())
+    }
+
+    /// Resolves the escape sequence following a `\`, the cursor is on the backslash.
+    fn escape(&mut self) -> Result<char, LunalaErrors> {
+        let escaped = match self.advance().cloned() {
+            None => { return self.error(ErrorTypes::UnterminatedString); }
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some(c @ ('"' | '\'' | '\\' | '`' | '$')) => c,
+            Some('u') => { return self.unicode_escape(); }
+            Some(c) => { return self.error(ErrorTypes::InvalidEscape(format!("\\{}", c))); }
+        };
+        Ok(escaped)
+    }
+
+    /// Resolves a `\u{1F600}` escape, the cursor is on the `u`.
+    fn unicode_escape(&mut self) -> Result<char, LunalaErrors> {
+        if self.advance() != Some(&'{') {
+            return self.error(ErrorTypes::InvalidEscape("\\u".to_string()));
+        }
+        let mut digits = String::new();
+        loop {
+            match self.advance().cloned() {
+                None => { return self.error(ErrorTypes::UnterminatedString); }
+                Some('}') => { break; }
+                Some(c) => { digits.push(c); }
+            }
+        }
+        let code_point = match digits.len() {
+            1..=6 => u32::from_str_radix(&digits, 16).ok(),
+            _ => None,
+        };
+        match code_point.and_then(char::from_u32) {
+            Some(c) => Ok(c),
+            None => self.error(ErrorTypes::InvalidEscape(format!("\\u{{{}}}", digits))),
+        }
+    }
+
+    fn add_template_string(&mut self, value: String) {
+        if !value.is_empty() {
+            self.add_token(Token::new(TokenType::TemplateString, Some(value), self.line));
+        }
+    }
+
+    pub fn error<T>(&self, error_types: ErrorTypes) -> Result<T, LunalaErrors> {
+        Err(LunalaErrors::new(error_types, self.line))
     }
 
     pub fn is_digit(&self, character: Option<&char>) -> bool {
@@ -137,25 +350,121 @@ impl Scanner {
         }
     }
 
-    pub fn number(&mut self) {
+    /// Scans a number literal, the cursor is on its first digit.
+    /// Decimal literals may have a fraction, an exponent (`6.02e23`) and a `d` suffix for exact decimals,
+    /// integers may instead use a `0x`, `0b` or `0o` prefix. Digits can be grouped with `_`, like `1_000_000`.
+    pub fn number(&mut self) -> Result<(), LunalaErrors> {
         let start = self.cursor;
-        while self.is_digit(self.peek()) {
-            let _ = self.advance();
+        let radix = match (self.current(), self.peek()) {
+            (Some('0'), Some('x')) => Some(16),
+            (Some('0'), Some('b')) => Some(2),
+            (Some('0'), Some('o')) => Some(8),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            return self.radix_number(radix);
         }
-        if self.peek() == Some(&'.') {
+
+        let mut value = self.current().map(|c| c.to_string()).unwrap_or_default();
+        self.digits(&mut value, 10, start)?;
+        let mut token_type = TokenType::Integer;
+        // A dot only makes a float when digits follow it, `3.0` is a float and `3` an integer
+        if self.peek() == Some(&'.') && self.is_digit(self.peek_next()) {
+            token_type = TokenType::Float;
+            value.push('.');
             // Consume the dot
             let _ = self.advance();
-            while self.is_digit(self.peek()) {
+            self.digits(&mut value, 10, start)?;
+        }
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            token_type = TokenType::Float;
+            value.push('e');
+            let _ = self.advance();
+            if let Some(sign @ ('+' | '-')) = self.peek().cloned() {
+                value.push(sign);
                 let _ = self.advance();
             }
+            if !self.is_digit(self.peek()) {
+                return self.malformed_number(start, "expected digits in the exponent");
+            }
+            self.digits(&mut value, 10, start)?;
+        }
+        // A `d` suffix makes an exact decimal, `1.10d`
+        if self.peek() == Some(&'d') && !self.is_alpha_numeric(self.peek_next()) {
+            let _ = self.advance();
+            if value.contains('e') {
+                return self.malformed_number(start, "a decimal can't have an exponent");
+            }
+            token_type = TokenType::Decimal;
+        }
+        self.add_token(Token::new(token_type, Some(value), self.line));
+        Ok(())
+    }
+
+    /// Scans a `0x`, `0b` or `0o` integer, the cursor is on its leading `0`.
+    /// The token carries the value in decimal digits, like any other integer.
+    fn radix_number(&mut self, radix: u32) -> Result<(), LunalaErrors> {
+        let start = self.cursor;
+        // Consume the prefix letter
+        let _ = self.advance();
+        let mut value = String::new();
+        if !matches!(self.peek(), Some(c) if c.is_digit(radix)) {
+            return self.malformed_number(start, &format!("expected at least one {} digit", Self::radix_name(radix)));
+        }
+        let _ = self.advance();
+        value.push(*self.current().unwrap_or(&'0'));
+        self.digits(&mut value, radix, start)?;
+        if let Some(c) = self.peek().cloned().filter(|c| c.is_alphanumeric()) {
+            let _ = self.advance();
+            return self.malformed_number(start, &format!("`{}` is not a {} digit", c, Self::radix_name(radix)));
+        }
+        match BigInt::parse_radix(&value, radix) {
+            Some(integer) => {
+                self.add_token(Token::new(TokenType::Integer, Some(integer.to_string()), self.line));
+                Ok(())
+            }
+            None => self.malformed_number(start, &format!("expected {} digits", Self::radix_name(radix))),
+        }
+    }
+
+    fn radix_name(radix: u32) -> &'static str {
+        match radix {
+            2 => "binary",
+            8 => "octal",
+            16 => "hexadecimal",
+            _ => "decimal",
         }
-        let value: String = self.source[start..self.cursor+1].iter().collect();
-        self.add_token(Token::new(TokenType::Number, Some(value), self.cursor));
+    }
+
+    /// Consumes the digits following the current one into `value`, skipping `_` separators.
+    /// A separator has to sit between two digits.
+    fn digits(&mut self, value: &mut String, radix: u32, start: usize) -> Result<(), LunalaErrors> {
+        loop {
+            match self.peek().cloned() {
+                Some(c) if c.is_digit(radix) => {
+                    value.push(c);
+                    let _ = self.advance();
+                }
+                Some('_') => {
+                    let _ = self.advance();
+                    if !matches!(self.peek(), Some(c) if c.is_digit(radix)) {
+                        return self.malformed_number(start, "`_` has to sit between two digits");
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn malformed_number<T>(&self, start: usize, reason: &str) -> Result<T, LunalaErrors> {
+        let end = (self.cursor + 1).min(self.source.len());
+        let literal: String = self.source[start..end].iter().collect();
+        self.error(ErrorTypes::MalformedNumber(literal, reason.to_string()))
     }
 
     pub fn is_alpha_numeric(&self, character: Option<&char>) -> bool {
         match character {
-            Some(c) => c.is_alphanumeric(),
+            Some(c) => c.is_alphanumeric() || *c == '_',
             None => false,
         }
     }
@@ -169,10 +478,10 @@ impl Scanner {
         let value: String = self.source[start..self.cursor+1].iter().collect();
         match keywords.get(value.clone()) {
             Some(token_type) => {
-                self.add_token(Token::new(token_type.clone(), None, self.cursor));
+                self.add_token(Token::new(token_type.clone(), None, self.line));
             }
             None => {
-                self.add_token(Token::new(TokenType::Identifier, Some(value), self.cursor));
+                self.add_token(Token::new(TokenType::Identifier, Some(value), self.line));
             }
         }
     }
@@ -181,4 +490,91 @@ impl Scanner {
         self.tokens.clone()
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The type and lexeme of every token but the final `EOF`.
+    fn scan(source: &str) -> Vec<(TokenType, String)> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        tokens[..tokens.len() - 1].iter().map(|token| (token.token_type(), token.access_lexeme())).collect()
+    }
+
+    fn single(source: &str) -> (TokenType, String) {
+        let mut tokens = scan(source);
+        assert_eq!(tokens.len(), 1, "`{}` should scan to a single token", source);
+        tokens.remove(0)
+    }
+
+    fn error(source: &str) -> String {
+        Scanner::new(source).scan_tokens().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn radix_prefixes() {
+        assert_eq!(single("0xff"), (TokenType::Integer, "255".to_string()));
+        assert_eq!(single("0xDEAD_BEEF"), (TokenType::Integer, "3735928559".to_string()));
+        assert_eq!(single("0b1010"), (TokenType::Integer, "10".to_string()));
+        assert_eq!(single("0o17"), (TokenType::Integer, "15".to_string()));
+        assert_eq!(single("0xffffffffffffffffff"), (TokenType::Integer, "4722366482869645213695".to_string()));
+        assert!(error("0x").contains("expected at least one hexadecimal digit"));
+        assert!(error("0b102").contains("`2` is not a binary digit"));
+    }
+
+    #[test]
+    fn digit_separators() {
+        assert_eq!(single("1_000_000"), (TokenType::Integer, "1000000".to_string()));
+        assert_eq!(single("3.141_592"), (TokenType::Float, "3.141592".to_string()));
+        assert!(error("1__000").contains("`_` has to sit between two digits"));
+        assert!(error("1_").contains("`_` has to sit between two digits"));
+    }
+
+    #[test]
+    fn exponents_and_decimals() {
+        assert_eq!(single("6.02e23"), (TokenType::Float, "6.02e23".to_string()));
+        assert_eq!(single("1E-5"), (TokenType::Float, "1e-5".to_string()));
+        assert_eq!(single("2e+3"), (TokenType::Float, "2e+3".to_string()));
+        assert_eq!(single("1.10d"), (TokenType::Decimal, "1.10".to_string()));
+        assert!(error("1e").contains("expected digits in the exponent"));
+        assert!(error("1e5d").contains("a decimal can't have an exponent"));
+    }
+
+    #[test]
+    fn a_dot_without_digits_is_not_a_fraction() {
+        assert_eq!(scan("3.x"), vec![
+            (TokenType::Integer, "3".to_string()),
+            (TokenType::Dot, "Dot".to_string()),
+            (TokenType::Identifier, "x".to_string()),
+        ]);
+        assert_eq!(scan("1..5")[1].0, TokenType::DotDot);
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(single(r#""a\nb\tc\r\0""#), (TokenType::String, "a\nb\tc\r\0".to_string()));
+        assert_eq!(single(r#""\"\'\\\`\$""#), (TokenType::String, "\"'\\`$".to_string()));
+        assert_eq!(single(r#""\u{48}\u{1F600}""#), (TokenType::String, "H\u{1F600}".to_string()));
+        assert_eq!(single(r#"r"C:\new\dir""#), (TokenType::String, r"C:\new\dir".to_string()));
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        assert!(error(r#""\q""#).contains("Invalid escape sequence `\\q`"));
+        assert!(error(r#""\u41""#).contains("Invalid escape sequence `\\u`"));
+        assert!(error(r#""\u{110000}""#).contains("Invalid escape sequence `\\u{110000}`"));
+        assert!(error(r#""\u{1234567}""#).contains("Invalid escape sequence `\\u{1234567}`"));
+        assert!(error(r#""abc\"#).contains("Unterminated"));
+    }
+
+    #[test]
+    fn char_literals() {
+        assert_eq!(single("'a'"), (TokenType::Char, "a".to_string()));
+        assert_eq!(single(r"'\n'"), (TokenType::Char, "\n".to_string()));
+        assert_eq!(single(r"'\''"), (TokenType::Char, "'".to_string()));
+        assert_eq!(single(r"'\u{e9}'"), (TokenType::Char, "é".to_string()));
+        assert!(error("'ab'").contains("Invalid character literal `'ab'`"));
+        assert!(error("''").contains("Invalid character literal `''`"));
+    }
+}
@@ -1,24 +1,281 @@
 use crate::expressions::ExpType;
+use crate::pattern::{MatchArm, Pattern};
+use crate::tokens::Token;
+use std::rc::Rc;
 
 pub type Statements = Vec<Statement>;
 
+#[derive(Debug)]
 pub enum Statement {
     Expression(Expression),
     Print(PrintExpression),
+    Let(LetStatement),
+    Block(BlockStatement),
+    If(IfStatement),
+    While(WhileStatement),
+    For(ForStatement),
+    Break(Token),
+    Continue(Token),
+    Function(Rc<FunctionStatement>),
+    Return(ReturnStatement),
+    Struct(Rc<StructStatement>),
+    Class(ClassStatement),
+    Enum(Rc<EnumStatement>),
+    Match(MatchStatement),
 }
 
 pub trait StatementTrait {
-    fn expression(&self) -> ExpType;
+    fn expression(&self) -> &ExpType;
 }
 
+#[derive(Debug)]
 pub struct Expression {
     expression: ExpType,
 }
 
+#[derive(Debug)]
 pub struct PrintExpression {
     expression: ExpType,
 }
 
+#[derive(Debug)]
+pub struct LetStatement {
+    keyword: Token,
+    pattern: Pattern,
+    initializer: ExpType,
+}
+
+#[derive(Debug)]
+pub struct BlockStatement {
+    statements: Statements,
+}
+
+#[derive(Debug)]
+pub struct IfStatement {
+    keyword: Token,
+    condition: ExpType,
+    then_branch: Box<Statement>,
+    else_branch: Option<Box<Statement>>,
+}
+
+impl IfStatement {
+    pub fn keyword(&self) -> &Token {
+        &self.keyword
+    }
+
+    pub fn then_branch(&self) -> &Statement {
+        &self.then_branch
+    }
+
+    pub fn else_branch(&self) -> Option<&Statement> {
+        self.else_branch.as_deref()
+    }
+}
+
+#[derive(Debug)]
+pub struct WhileStatement {
+    keyword: Token,
+    condition: ExpType,
+    body: Box<Statement>,
+}
+
+impl WhileStatement {
+    pub fn keyword(&self) -> &Token {
+        &self.keyword
+    }
+
+    pub fn body(&self) -> &Statement {
+        &self.body
+    }
+}
+
+/// A C-style `for (initializer; condition; increment) body` loop, every clause is optional.
+#[derive(Debug)]
+pub struct ForStatement {
+    keyword: Token,
+    initializer: Option<Box<Statement>>,
+    condition: Option<ExpType>,
+    increment: Option<ExpType>,
+    body: Box<Statement>,
+}
+
+impl ForStatement {
+    pub fn keyword(&self) -> &Token {
+        &self.keyword
+    }
+
+    pub fn initializer(&self) -> Option<&Statement> {
+        self.initializer.as_deref()
+    }
+
+    pub fn condition(&self) -> Option<&ExpType> {
+        self.condition.as_ref()
+    }
+
+    pub fn increment(&self) -> Option<&ExpType> {
+        self.increment.as_ref()
+    }
+
+    pub fn body(&self) -> &Statement {
+        &self.body
+    }
+}
+
+/// A `fn name(params) { body }` declaration or an anonymous `fn (params) { body }` expression,
+/// shared with the function objects created from it.
+#[derive(Debug)]
+pub struct FunctionStatement {
+    name: Option<Token>,
+    params: Vec<Token>,
+    body: Statements,
+}
+
+impl FunctionStatement {
+    pub fn name(&self) -> Option<&Token> {
+        self.name.as_ref()
+    }
+
+    /// The name used when printing the function, anonymous functions are shown as `anonymous`.
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.access_lexeme(),
+            None => "anonymous".to_string(),
+        }
+    }
+
+    pub fn params(&self) -> &Vec<Token> {
+        &self.params
+    }
+
+    pub fn body(&self) -> &Statements {
+        &self.body
+    }
+}
+
+/// A `struct Name { field, ... }` declaration, shared with the struct object created from it.
+#[derive(Debug)]
+pub struct StructStatement {
+    name: Token,
+    fields: Vec<Token>,
+}
+
+impl StructStatement {
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn fields(&self) -> &Vec<Token> {
+        &self.fields
+    }
+}
+
+/// A `class Name < Superclass { fn method() { ... } ... }` declaration.
+#[derive(Debug)]
+pub struct ClassStatement {
+    name: Token,
+    superclass: Option<Token>,
+    methods: Vec<Rc<FunctionStatement>>,
+}
+
+impl ClassStatement {
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn superclass(&self) -> Option<&Token> {
+        self.superclass.as_ref()
+    }
+
+    pub fn methods(&self) -> &Vec<Rc<FunctionStatement>> {
+        &self.methods
+    }
+}
+
+/// An `enum Name { Variant(field, ...), Unit, ... }` declaration, shared with the enum object created from it.
+#[derive(Debug)]
+pub struct EnumStatement {
+    name: Token,
+    variants: Vec<EnumVariant>,
+}
+
+/// A variant of an enum, unit variants like `Empty` have no `fields`.
+#[derive(Debug)]
+pub struct EnumVariant {
+    name: Token,
+    fields: Option<Vec<Token>>,
+}
+
+impl EnumStatement {
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn variants(&self) -> &Vec<EnumVariant> {
+        &self.variants
+    }
+}
+
+impl EnumVariant {
+    pub fn new(name: Token, fields: Option<Vec<Token>>) -> EnumVariant {
+        EnumVariant { name, fields }
+    }
+
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn fields(&self) -> Option<&Vec<Token>> {
+        self.fields.as_ref()
+    }
+}
+
+/// A `match` in statement position, its arms are statements so they can `return` or `break`.
+#[derive(Debug)]
+pub struct MatchStatement {
+    keyword: Token,
+    subject: ExpType,
+    arms: Vec<MatchArm<Statement>>,
+}
+
+impl MatchStatement {
+    pub fn keyword(&self) -> &Token {
+        &self.keyword
+    }
+
+    pub fn arms(&self) -> &Vec<MatchArm<Statement>> {
+        &self.arms
+    }
+}
+
+#[derive(Debug)]
+pub struct ReturnStatement {
+    keyword: Token,
+    value: Option<ExpType>,
+}
+
+impl ReturnStatement {
+    pub fn value(&self) -> Option<&ExpType> {
+        self.value.as_ref()
+    }
+}
+
+impl BlockStatement {
+    pub fn statements(&self) -> &Statements {
+        &self.statements
+    }
+}
+
+impl LetStatement {
+    pub fn keyword(&self) -> &Token {
+        &self.keyword
+    }
+
+    /// What the value is bound to, a plain `let x = ...` is a `Pattern::Binding`.
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+}
+
 impl Statement {
     fn get_string(&self) -> String {
         match self {
@@ -28,6 +285,42 @@ impl Statement {
             Statement::Print(expression) => {
                 format!("Print({})", expression)
             }
+            Statement::Let(statement) => {
+                format!("Let({})", statement)
+            }
+            Statement::Block(block) => {
+                format!("Block({})", block)
+            }
+            Statement::If(statement) => {
+                format!("If({})", statement)
+            }
+            Statement::While(statement) => {
+                format!("While({})", statement)
+            }
+            Statement::For(statement) => {
+                format!("For({})", statement)
+            }
+            Statement::Break(keyword) | Statement::Continue(keyword) => {
+                keyword.token_type().to_string()
+            }
+            Statement::Function(function) => {
+                format!("Function({})", function)
+            }
+            Statement::Return(statement) => {
+                format!("Return({})", statement)
+            }
+            Statement::Struct(statement) => {
+                format!("Struct({})", statement)
+            }
+            Statement::Class(statement) => {
+                format!("Class({})", statement)
+            }
+            Statement::Enum(statement) => {
+                format!("Enum({})", statement)
+            }
+            Statement::Match(statement) => {
+                format!("Match({})", statement)
+            }
         }
     }
     
@@ -38,6 +331,50 @@ impl Statement {
     pub fn new_expression(expression: ExpType) -> Expression {
         Expression { expression }
     }
+
+    pub fn new_let(keyword: Token, pattern: Pattern, initializer: ExpType) -> LetStatement {
+        LetStatement { keyword, pattern, initializer }
+    }
+
+    pub fn new_function(name: Option<Token>, params: Vec<Token>, body: Statements) -> FunctionStatement {
+        FunctionStatement { name, params, body }
+    }
+
+    pub fn new_struct(name: Token, fields: Vec<Token>) -> StructStatement {
+        StructStatement { name, fields }
+    }
+
+    pub fn new_class(name: Token, superclass: Option<Token>, methods: Vec<Rc<FunctionStatement>>) -> ClassStatement {
+        ClassStatement { name, superclass, methods }
+    }
+
+    pub fn new_enum(name: Token, variants: Vec<EnumVariant>) -> EnumStatement {
+        EnumStatement { name, variants }
+    }
+
+    pub fn new_match(keyword: Token, subject: ExpType, arms: Vec<MatchArm<Statement>>) -> MatchStatement {
+        MatchStatement { keyword, subject, arms }
+    }
+
+    pub fn new_return(keyword: Token, value: Option<ExpType>) -> ReturnStatement {
+        ReturnStatement { keyword, value }
+    }
+
+    pub fn new_block(statements: Statements) -> BlockStatement {
+        BlockStatement { statements }
+    }
+
+    pub fn new_if(keyword: Token, condition: ExpType, then_branch: Statement, else_branch: Option<Statement>) -> IfStatement {
+        IfStatement { keyword, condition, then_branch: Box::new(then_branch), else_branch: else_branch.map(Box::new) }
+    }
+
+    pub fn new_while(keyword: Token, condition: ExpType, body: Statement) -> WhileStatement {
+        WhileStatement { keyword, condition, body: Box::new(body) }
+    }
+
+    pub fn new_for(keyword: Token, initializer: Option<Statement>, condition: Option<ExpType>, increment: Option<ExpType>, body: Statement) -> ForStatement {
+        ForStatement { keyword, initializer: initializer.map(Box::new), condition, increment, body: Box::new(body) }
+    }
 }
 
 impl std::fmt::Display for Statement {
@@ -58,27 +395,128 @@ impl std::fmt::Display for PrintExpression {
     }
 }
 
+impl std::fmt::Display for LetStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} = {}", self.pattern, self.initializer)
+    }
+}
+
+impl std::fmt::Display for BlockStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let statements: Vec<String> = self.statements.iter().map(|statement| statement.to_string()).collect();
+        write!(f, "{}", statements.join(", "))
+    }
+}
+
+impl std::fmt::Display for IfStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}, {}", self.condition, self.then_branch)?;
+        if let Some(ref else_branch) = self.else_branch { write!(f, ", {}", else_branch)? }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for WhileStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}, {}", self.condition, self.body)
+    }
+}
+
+impl std::fmt::Display for ForStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(ref initializer) = self.initializer { write!(f, "{}", initializer)? }
+        write!(f, "; ")?;
+        if let Some(ref condition) = self.condition { write!(f, "{}", condition)? }
+        write!(f, "; ")?;
+        if let Some(ref increment) = self.increment { write!(f, "{}", increment)? }
+        write!(f, ", {}", self.body)
+    }
+}
+
+impl std::fmt::Display for FunctionStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|param| param.access_lexeme()).collect();
+        let body: Vec<String> = self.body.iter().map(|statement| statement.to_string()).collect();
+        write!(f, "{}({}), {}", self.display_name(), params.join(", "), body.join(", "))
+    }
+}
+
+impl std::fmt::Display for StructStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fields: Vec<String> = self.fields.iter().map(|field| field.access_lexeme()).collect();
+        write!(f, "{} {{ {} }}", self.name.access_lexeme(), fields.join(", "))
+    }
+}
+
+impl std::fmt::Display for ClassStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name.access_lexeme())?;
+        if let Some(ref superclass) = self.superclass { write!(f, " < {}", superclass.access_lexeme())? }
+        let methods: Vec<String> = self.methods.iter().map(|method| method.to_string()).collect();
+        write!(f, ", {}", methods.join(", "))
+    }
+}
+
+impl std::fmt::Display for EnumStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let variants: Vec<String> = self.variants.iter().map(|variant| match &variant.fields {
+            Some(fields) => {
+                let fields: Vec<String> = fields.iter().map(|field| field.access_lexeme()).collect();
+                format!("{}({})", variant.name.access_lexeme(), fields.join(", "))
+            }
+            None => variant.name.access_lexeme(),
+        }).collect();
+        write!(f, "{} {{ {} }}", self.name.access_lexeme(), variants.join(", "))
+    }
+}
+
+impl std::fmt::Display for MatchStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let arms: Vec<String> = self.arms.iter().map(|arm| arm.to_string()).collect();
+        write!(f, "{}, {}", self.subject, arms.join(", "))
+    }
+}
+
+impl std::fmt::Display for ReturnStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.keyword.token_type())?;
+        if let Some(ref value) = self.value { write!(f, " {}", value)? }
+        Ok(())
+    }
+}
+
 impl StatementTrait for Expression {
-    fn expression(&self) -> ExpType {
-        self.expression.clone()
+    fn expression(&self) -> &ExpType {
+        &self.expression
     }
 }
 
 impl StatementTrait for PrintExpression {
-    fn expression(&self) -> ExpType {
-        self.expression.clone()
+    fn expression(&self) -> &ExpType {
+        &self.expression
     }
 }
 
-impl StatementTrait for Statement {
-    fn expression(&self) -> ExpType {
-        match self {
-            Statement::Expression(e) => {
-                e.expression()
-            }
-            Statement::Print(p) => {
-                p.expression()
-            }
-        }
+impl StatementTrait for LetStatement {
+    fn expression(&self) -> &ExpType {
+        &self.initializer
+    }
+}
+
+impl StatementTrait for MatchStatement {
+    fn expression(&self) -> &ExpType {
+        &self.subject
     }
-}
\ No newline at end of file
+}
+
+impl StatementTrait for IfStatement {
+    fn expression(&self) -> &ExpType {
+        &self.condition
+    }
+}
+
+impl StatementTrait for WhileStatement {
+    fn expression(&self) -> &ExpType {
+        &self.condition
+    }
+}
@@ -4,18 +4,22 @@ use std::collections::HashMap;
 pub struct Token {
     token_type: TokenType,
     lexeme: Option<String>,
-    _line: usize,
+    line: usize,
 }
 
 impl Token {
     pub fn new(token_type: TokenType, lexeme: Option<String>, line: usize) -> Token {
-        Token { token_type, lexeme, _line: line }
+        Token { token_type, lexeme, line }
     }
     
     pub fn token_type(&self) -> TokenType {
         self.token_type.clone()
     }
     
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
     pub fn access_lexeme(&self) -> String {
         match self.lexeme.clone() {
             None => { 
@@ -35,6 +39,7 @@ impl std::fmt::Display for Token {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum TokenType {
     Slash, Comment,
 
@@ -42,17 +47,22 @@ pub enum TokenType {
     LeftSquareBracket, RightSquareBracket,
     LeftCurlyBracket, RightCurlyBracket,
 
-    Plus, Minus, Star, Equals, DoubleEquals, Bang, Percent, Colon, Semicolon, Dot,
+    Plus, Minus, Star, DoubleStar, Equals, DoubleEquals, Bang, Percent, Colon, Semicolon, Comma, Dot, DotDot, DotDotEquals, FatArrow,
+    QuestionDot, DoubleQuestion,
     LessThan, GreaterThan, LessEquals, GreaterEquals, BangEquals,
+    Ampersand, Pipe, Caret, Tilde, ShiftLeft, ShiftRight,
+    PlusEquals, MinusEquals, StarEquals, SlashEquals, PercentEquals,
     //DoubleQuote,
-    SingleQuote, AltQuote,
+    AltQuote,
+    TemplateString, InterpolationStart, InterpolationEnd,
 
-    String, Number, Identifier,
-    True, False,
+    String, Char, Integer, Float, Decimal, Identifier,
+    True, False, Nil,
 
-    And, Or, If, Let,
+    And, Or, If, Else, Let,
+    While, For, Break, Continue, Return,
 
-    Package, Function, Print,
+    Package, Function, Print, Struct, Class, SelfKeyword, Super, Enum, Match,
     
     EOF,
 }
@@ -71,6 +81,7 @@ impl TokenType {
             TokenType::Plus => {"Plus"}
             TokenType::Minus => {"Minus"}
             TokenType::Star => {"Star"}
+            TokenType::DoubleStar => {"DoubleStar"}
             TokenType::Equals => {"Equals"}
             TokenType::DoubleEquals => {"DoubleEquals"}
             TokenType::Bang => {"Bang"}
@@ -78,29 +89,120 @@ impl TokenType {
             TokenType::Percent => {"Percent"}
             TokenType::Colon => {"Colon"}
             TokenType::Semicolon => {"Semicolon"}
+            TokenType::Comma => {"Comma"}
             TokenType::Package => {"Package"}
             TokenType::Function => {"Function"}
             TokenType::Print => {"Print"}
+            TokenType::Struct => {"Struct"}
+            TokenType::Class => {"Class"}
+            TokenType::SelfKeyword => {"Self"}
+            TokenType::Super => {"Super"}
+            TokenType::Enum => {"Enum"}
+            TokenType::Match => {"Match"}
             TokenType::LessThan => {"LessThan"}
             TokenType::GreaterThan => {"GreaterThan"}
             TokenType::LessEquals => {"LessThanOrEquals"}
             TokenType::GreaterEquals => {"GreaterThanOrEquals"}
+            TokenType::Ampersand => {"Ampersand"}
+            TokenType::Pipe => {"Pipe"}
+            TokenType::Caret => {"Caret"}
+            TokenType::Tilde => {"Tilde"}
+            TokenType::ShiftLeft => {"ShiftLeft"}
+            TokenType::ShiftRight => {"ShiftRight"}
+            TokenType::PlusEquals => {"PlusEquals"}
+            TokenType::MinusEquals => {"MinusEquals"}
+            TokenType::StarEquals => {"StarEquals"}
+            TokenType::SlashEquals => {"SlashEquals"}
+            TokenType::PercentEquals => {"PercentEquals"}
             //TokenType::DoubleQuote => {"DoubleQuote"}
-            TokenType::SingleQuote => {"SingleQuote"}
             TokenType::AltQuote => {"AltQuote"}
+            TokenType::TemplateString => {"TemplateString"}
+            TokenType::InterpolationStart => {"InterpolationStart"}
+            TokenType::InterpolationEnd => {"InterpolationEnd"}
             TokenType::String => {"String"}
-            TokenType::Number => {"Number"}
+            TokenType::Char => {"Char"}
+            TokenType::Integer => {"Integer"}
+            TokenType::Float => {"Float"}
+            TokenType::Decimal => {"Decimal"}
             TokenType::Dot => {"Dot"}
+            TokenType::DotDot => {"DotDot"}
+            TokenType::DotDotEquals => {"DotDotEquals"}
+            TokenType::FatArrow => {"FatArrow"}
+            TokenType::QuestionDot => {"QuestionDot"}
+            TokenType::DoubleQuestion => {"DoubleQuestion"}
             TokenType::And => {"And"}
             TokenType::Or => {"Or"}
             TokenType::If => {"If"}
+            TokenType::Else => {"Else"}
             TokenType::Let => {"Let"}
+            TokenType::While => {"While"}
+            TokenType::For => {"For"}
+            TokenType::Break => {"Break"}
+            TokenType::Continue => {"Continue"}
+            TokenType::Return => {"Return"}
             TokenType::Identifier => {"Identifier"}
             TokenType::EOF => {"End of File"}
             TokenType::True => {"True"}
             TokenType::False => {"False"}
+            TokenType::Nil => {"Nil"}
+        }.to_owned()
+    }
+
+    /// How an operator is written in source, used in error messages. Other tokens fall back to their name.
+    pub fn symbol(&self) -> String {
+        match self {
+            TokenType::Plus => {"+"}
+            TokenType::Minus => {"-"}
+            TokenType::Star => {"*"}
+            TokenType::DoubleStar => {"**"}
+            TokenType::Slash => {"/"}
+            TokenType::Percent => {"%"}
+            TokenType::Bang => {"!"}
+            TokenType::DoubleEquals => {"=="}
+            TokenType::BangEquals => {"!="}
+            TokenType::LessThan => {"<"}
+            TokenType::GreaterThan => {">"}
+            TokenType::LessEquals => {"<="}
+            TokenType::GreaterEquals => {">="}
+            TokenType::Ampersand => {"&"}
+            TokenType::Pipe => {"|"}
+            TokenType::Caret => {"^"}
+            TokenType::Tilde => {"~"}
+            TokenType::ShiftLeft => {"<<"}
+            TokenType::ShiftRight => {">>"}
+            TokenType::DotDot => {".."}
+            TokenType::DotDotEquals => {"..="}
+            TokenType::FatArrow => {"=>"}
+            TokenType::Equals => {"="}
+            TokenType::PlusEquals => {"+="}
+            TokenType::MinusEquals => {"-="}
+            TokenType::StarEquals => {"*="}
+            TokenType::SlashEquals => {"/="}
+            TokenType::PercentEquals => {"%="}
+            TokenType::And => {"and"}
+            TokenType::Or => {"or"}
+            TokenType::QuestionDot => {"?."}
+            TokenType::DoubleQuestion => {"??"}
+            _ => { return self.map() }
         }.to_owned()
     }
+
+    /// Operators that only apply to integers.
+    pub fn is_bitwise(&self) -> bool {
+        matches!(self, TokenType::Ampersand | TokenType::Pipe | TokenType::Caret | TokenType::ShiftLeft | TokenType::ShiftRight)
+    }
+
+    /// The binary operator a compound assignment like `+=` applies before assigning.
+    pub fn compound_operator(&self) -> Option<TokenType> {
+        match self {
+            TokenType::PlusEquals => Some(TokenType::Plus),
+            TokenType::MinusEquals => Some(TokenType::Minus),
+            TokenType::StarEquals => Some(TokenType::Star),
+            TokenType::SlashEquals => Some(TokenType::Slash),
+            TokenType::PercentEquals => Some(TokenType::Percent),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for TokenType {
@@ -124,12 +226,25 @@ impl ReservedKeywords {
         self.insert("and", TokenType::And);
         self.insert("or", TokenType::Or);
         self.insert("if", TokenType::If);
+        self.insert("else", TokenType::Else);
         self.insert("let", TokenType::Let);
+        self.insert("while", TokenType::While);
+        self.insert("for", TokenType::For);
+        self.insert("break", TokenType::Break);
+        self.insert("continue", TokenType::Continue);
         self.insert("fn", TokenType::Function);
+        self.insert("return", TokenType::Return);
+        self.insert("struct", TokenType::Struct);
+        self.insert("class", TokenType::Class);
+        self.insert("self", TokenType::SelfKeyword);
+        self.insert("super", TokenType::Super);
+        self.insert("enum", TokenType::Enum);
+        self.insert("match", TokenType::Match);
         self.insert("package", TokenType::Package);
         self.insert("print", TokenType::Print);
         self.insert("true", TokenType::True);
         self.insert("false", TokenType::False);
+        self.insert("nil", TokenType::Nil);
     }
 
     pub fn insert(&mut self, key: &str, value: TokenType) {